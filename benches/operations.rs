@@ -0,0 +1,103 @@
+#![feature(test)]
+
+extern crate std;
+extern crate test;
+
+use header_vec::*;
+use test::Bencher;
+
+const N_VECS: usize = 10_000;
+
+fn build_vecs(b: &mut Bencher, make: impl Fn() -> HeaderVec<(), usize>) {
+    b.iter(|| {
+        let mut vecs = std::vec::Vec::with_capacity(N_VECS);
+        for _ in 0..N_VECS {
+            let mut v = make();
+            for i in 0..6 {
+                v.push(i);
+            }
+            vecs.push(v);
+        }
+        vecs
+    });
+}
+
+#[bench]
+fn test_header_vec_build_many_default(b: &mut Bencher) {
+    build_vecs(b, || HeaderVec::new(()));
+}
+
+#[bench]
+fn test_header_vec_build_many_min_capacity(b: &mut Bencher) {
+    build_vecs(b, || HeaderVec::new_with_min_capacity((), 8));
+}
+
+fn big_u32_vec() -> HeaderVec<(), u32> {
+    HeaderVec::from_fn((), 1 << 16, |i| i as u32)
+}
+
+#[bench]
+fn test_header_vec_clone(b: &mut Bencher) {
+    let v = big_u32_vec();
+    b.iter(|| v.clone());
+}
+
+#[bench]
+fn test_header_vec_clone_copy(b: &mut Bencher) {
+    let v = big_u32_vec();
+    b.iter(|| v.clone_copy());
+}
+
+#[bench]
+fn test_header_vec_rotate_left(b: &mut Bencher) {
+    let mut v = big_u32_vec();
+    b.iter(|| v.as_mut_slice().rotate_left(7));
+}
+
+#[bench]
+fn test_header_vec_rotate_left_copy(b: &mut Bencher) {
+    let mut v = big_u32_vec();
+    b.iter(|| v.rotate_left_copy(7));
+}
+
+const ZEROED_LEN: usize = 1 << 22;
+
+#[bench]
+fn test_header_vec_zeroed_len(b: &mut Bencher) {
+    b.iter(|| unsafe { HeaderVec::<(), u8>::with_capacity_zeroed_len(ZEROED_LEN, ()) });
+}
+
+#[bench]
+fn test_header_vec_alloc_and_fill(b: &mut Bencher) {
+    b.iter(|| HeaderVec::from_elem((), 0u8, ZEROED_LEN));
+}
+
+const PUSH_BATCH: usize = 1000;
+
+#[bench]
+fn test_header_vec_push_naive(b: &mut Bencher) {
+    b.iter(|| {
+        let mut v = HeaderVec::<(), usize>::new(());
+        for i in 0..PUSH_BATCH {
+            v.push(i);
+        }
+        v
+    });
+}
+
+#[bench]
+fn test_header_vec_push_reserving(b: &mut Bencher) {
+    b.iter(|| {
+        let mut v = HeaderVec::<(), usize>::new(());
+        for i in 0..PUSH_BATCH {
+            v.push_reserving(i, PUSH_BATCH - i);
+        }
+        v
+    });
+}
+
+#[bench]
+fn test_header_vec_retain_all(b: &mut Bencher) {
+    let mut v = big_u32_vec();
+    b.iter(|| v.retain(|&x| x != u32::MAX));
+}
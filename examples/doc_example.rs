@@ -19,7 +19,7 @@ fn main() {
     );
     println!(
         "All of the data, like our header `{:?}`, the length of the vector: `{}`,",
-        &*hv,
+        *hv,
         hv.len()
     );
     println!(
@@ -2,22 +2,147 @@
 
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{boxed::Box, vec::Vec};
+
 use core::{
     cmp,
     fmt::Debug,
     marker::PhantomData,
-    mem::{self, ManuallyDrop},
-    ops::{Deref, DerefMut, Index, IndexMut},
-    ptr,
+    mem::{self, ManuallyDrop, MaybeUninit},
+    ops::{Bound, Deref, DerefMut, Index, IndexMut, Range, RangeBounds},
+    ptr::{self, NonNull},
     slice::SliceIndex,
 };
 
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+#[cfg(feature = "atomic_append")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// This is `repr(C)` so that the layout only depends on the size and alignment of `H`, which lets
+// `HeaderVec<MaybeUninit<H>, T>` and `HeaderVec<H, T>` share the same allocation.
+#[repr(C)]
 struct HeaderVecHeader<H> {
     head: H,
+    /// The capacity, with flags stored in its highest bits, see [`CAPACITY_MASK`].
     capacity: usize,
+    #[cfg(feature = "atomic_append")]
+    len: AtomicUsize,
+    #[cfg(not(feature = "atomic_append"))]
     len: usize,
+    /// Counts the successful atomic appends, see [`HeaderVec::version_atomic`].
+    #[cfg(feature = "atomic_append")]
+    version: AtomicUsize,
+}
+
+/// Set in the capacity word when the elements don't start at [`HeaderVec::min_offset`]. The actual
+/// offset is then stored in a `usize` right after the header, inside the padding reserved by
+/// [`HeaderVec::with_header_reserve`], so regular vectors never need to load it from memory.
+const STORED_OFFSET_FLAG: usize = 1 << (usize::BITS - 1);
+
+/// Set in the capacity word for vectors created with [`HeaderVec::with_capacity_exact_growth`].
+const EXACT_GROWTH_FLAG: usize = 1 << (usize::BITS - 2);
+
+/// The bits of the capacity word that hold the capacity. A larger capacity could never be
+/// allocated anyway, so the remaining bits are free to hold flags.
+const CAPACITY_MASK: usize = !(STORED_OFFSET_FLAG | EXACT_GROWTH_FLAG);
+
+/// The size of the stack buffer used by [`HeaderVec::rotate_left_copy`] and
+/// [`HeaderVec::rotate_right_copy`].
+const ROTATE_BUFFER_BYTES: usize = 256;
+
+/// A breakdown of the memory used by a [`HeaderVec`], as returned by [`HeaderVec::memory_report`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// The size of the header, including the length and capacity stored alongside it.
+    pub header_bytes: usize,
+    /// The bytes between the end of the header and the start of the elements.
+    pub padding_bytes: usize,
+    /// The bytes occupied by the elements currently in the vector.
+    pub element_bytes_used: usize,
+    /// The bytes reserved for elements that can be added without reallocating.
+    pub element_bytes_spare: usize,
+    /// The size of the whole allocation.
+    pub total_bytes: usize,
+}
+
+/// What happened to the allocation during [`HeaderVec::reserve_detailed`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReserveOutcome {
+    /// There already was enough capacity.
+    NoGrowthNeeded,
+    /// The capacity grew without moving the memory.
+    GrewInPlace,
+    /// The capacity grew and the memory was moved to a new location.
+    /// You are responsible for updating the weak nodes.
+    Moved { old: *const () },
+}
+
+/// The error returned by [`HeaderVec::try_index`] when the index is out of bounds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IndexError {
+    /// The index that was requested.
+    pub index: usize,
+    /// The length of the vector at the time of the access.
+    pub len: usize,
+}
+
+impl core::fmt::Display for IndexError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "index {} is out of bounds for a HeaderVec of length {}",
+            self.index, self.len
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IndexError {}
+
+// The growth path always reports failures with this error internally, but it is only part of the
+// public API with the `fallible_alloc` feature.
+mod try_reserve_error {
+    /// The error returned when the vector can't grow, with the `fallible_alloc` feature.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum TryReserveError {
+        /// The requested capacity doesn't fit in the address space.
+        CapacityOverflow,
+        /// The allocator failed to provide the memory.
+        AllocError {
+            /// The layout that was requested from the allocator.
+            layout: alloc::alloc::Layout,
+        },
+    }
+
+    impl core::fmt::Display for TryReserveError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                TryReserveError::CapacityOverflow => {
+                    f.write_str("HeaderVec capacity exceeds the maximum allocation size")
+                }
+                TryReserveError::AllocError { layout } => write!(
+                    f,
+                    "memory allocation of {} bytes for HeaderVec failed",
+                    layout.size()
+                ),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for TryReserveError {}
 }
 
+#[cfg(feature = "fallible_alloc")]
+pub use try_reserve_error::TryReserveError;
+#[cfg(not(feature = "fallible_alloc"))]
+use try_reserve_error::TryReserveError;
+
 /// A vector with a header of your choosing behind a thin pointer
 ///
 /// # Example
@@ -45,20 +170,254 @@ pub struct HeaderVec<H, T> {
     _phantom: PhantomData<H>,
 }
 
+// SAFETY: `HeaderVec` owns its header and elements the same way a `Box` of them would, the raw
+// pointer only keeps the compiler from inferring that. Sending it to another thread moves that
+// ownership along, and a shared `&HeaderVec` only gives out shared references to `H` and `T`. The
+// `_atomic` appends that write through `&self` are unsafe and require a single producer, with the
+// length published through an `AtomicUsize`. `HeaderVecWeak` aliases the allocation without owning
+// it, so it opts out of both traits and these impls don't let a weak reference reach another thread.
+unsafe impl<H: Send, T: Send> Send for HeaderVec<H, T> {}
+
+// SAFETY: see the `Send` impl above.
+unsafe impl<H: Sync, T: Sync> Sync for HeaderVec<H, T> {}
+
 impl<H, T> HeaderVec<H, T> {
     pub fn new(head: H) -> Self {
         Self::with_capacity(1, head)
     }
 
+    /// Starts building a `HeaderVec` whose capacity is the sum of several hints, so that it is
+    /// allocated only once. See [`HeaderVecBuilder`].
+    #[inline(always)]
+    pub fn builder(head: H) -> HeaderVecBuilder<H, T> {
+        HeaderVecBuilder {
+            head,
+            capacity: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates an empty `HeaderVec` with a clone of a borrowed header.
+    #[inline]
+    pub fn new_cloned_header(head: &H) -> Self
+    where
+        H: Clone,
+    {
+        Self::new(head.clone())
+    }
+
+    /// Creates a `HeaderVec` that starts with room for `min_capacity` elements instead of one, to
+    /// avoid the first few reallocations when many small vectors of a similar size are built.
+    #[inline(always)]
+    pub fn new_with_min_capacity(head: H, min_capacity: usize) -> Self {
+        Self::with_capacity(min_capacity, head)
+    }
+
+    /// Creates a `HeaderVec` with room for at least `capacity` elements.
+    ///
+    /// The capacity is never less than [`HeaderVec::min_useful_capacity`], so a `capacity` of 0 is
+    /// treated as 1. The allocation always holds the header, the padding that aligns the elements,
+    /// and `capacity` elements, so the requested capacity is exactly what can be pushed without
+    /// reallocating.
     pub fn with_capacity(capacity: usize, head: H) -> Self {
-        assert!(capacity > 0, "HeaderVec capacity cannot be 0");
-        // Allocate the initial memory, which is unititialized.
-        let layout = Self::layout(capacity);
-        let ptr = unsafe { alloc::alloc::alloc(layout) } as *mut T;
+        Self::with_capacity_and_offset(capacity, Self::min_offset(), head, false)
+    }
+
+    /// Creates a `HeaderVec` holding `len` elements whose bytes are all zero, by asking the allocator
+    /// for zeroed memory. For large buffers this is usually much faster than allocating and then
+    /// filling, since the operating system can hand out pages that are already zeroed.
+    ///
+    /// # Safety
+    ///
+    /// A value of `T` whose bytes are all zero must be valid, as is the case for the integer types.
+    pub unsafe fn with_capacity_zeroed_len(len: usize, head: H) -> Self {
+        let mut this = Self::with_capacity_and_offset(len, Self::min_offset(), head, true);
+        this.set_len_exact(len);
+        this
+    }
+
+    /// The smallest capacity a `HeaderVec` is ever allocated with. Zero-sized elements never need
+    /// to be reallocated, so their capacity is `usize::MAX`. Otherwise there must be room for at
+    /// least one element, since growing doubles the capacity.
+    #[inline(always)]
+    pub fn min_useful_capacity() -> usize {
+        if Self::element_size_nonzero() {
+            1
+        } else {
+            usize::MAX
+        }
+    }
+
+    /// Creates a `HeaderVec` with `len` elements, where each element is produced by calling `f`
+    /// with its index. This is the `HeaderVec` equivalent of [`core::array::from_fn`].
+    pub fn from_fn(head: H, len: usize, mut f: impl FnMut(usize) -> T) -> Self {
+        let mut this = Self::with_capacity(cmp::max(len, 1), head);
+        for index in 0..len {
+            this.push(f(index));
+        }
+        this
+    }
+
+    /// Creates a `HeaderVec` holding `n` clones of `value`, like `vec![value; n]`.
+    pub fn from_elem(head: H, value: T, n: usize) -> Self
+    where
+        T: Clone,
+    {
+        let mut this = Self::with_capacity(cmp::max(n, 1), head);
+        if n > 0 {
+            for _ in 1..n {
+                this.push(value.clone());
+            }
+            this.push(value);
+        }
+        this
+    }
+
+    /// Allocates a `HeaderVec` with room for `capacity` elements whose length is already `len`,
+    /// so the elements can be written in place, for instance in parallel through [`Self::as_mut_ptr`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than `capacity`.
+    ///
+    /// # Safety
+    ///
+    /// The elements `0..len` are uninitialized. Every one of them must be written before any of them
+    /// is read or the vector is dropped, otherwise the behavior is undefined.
+    pub unsafe fn with_capacity_assume_len(capacity: usize, len: usize, head: H) -> Self {
+        assert!(len <= capacity, "len is greater than capacity");
+        let mut this = Self::with_capacity(capacity, head);
+        this.set_len(len);
+        this
+    }
+
+    /// Creates a `HeaderVec` from an iterator of results, stopping at the first error.
+    ///
+    /// If an `Err` is encountered, the partially built vector is dropped and the error is returned.
+    pub fn try_from_iter_with_header<I, E>(head: H, iter: I) -> Result<Self, E>
+    where
+        I: IntoIterator<Item = Result<T, E>>,
+    {
+        let iter = iter.into_iter();
+        let mut this = Self::with_capacity(cmp::max(iter.size_hint().0, 1), head);
+        for item in iter {
+            this.push(item?);
+        }
+        Ok(this)
+    }
+
+    /// Allocates a `HeaderVec` without initializing its header, for headers that are expensive to
+    /// construct or that need to be written in place. The returned [`UninitHeaderVec`] only gives
+    /// access to the header, and turns into a `HeaderVec` with [`UninitHeaderVec::assume_header_init`].
+    pub fn with_capacity_uninit_header(capacity: usize) -> UninitHeaderVec<H, T> {
+        UninitHeaderVec {
+            header_vec: HeaderVec::with_capacity(capacity, MaybeUninit::uninit()),
+        }
+    }
+
+    /// Creates a `HeaderVec` with the elements of a boxed slice, freeing the box's allocation.
+    ///
+    /// The elements have to be copied, since the memory of the box has no room for the header in
+    /// front of them.
+    pub fn from_boxed_slice_with_header(head: H, boxed: Box<[T]>) -> Self {
+        let mut elements = boxed.into_vec();
+        let mut this = Self::with_capacity(elements.len(), head);
+        unsafe {
+            ptr::copy_nonoverlapping(elements.as_ptr(), this.start_ptr_mut(), elements.len());
+            this.set_len_exact(elements.len());
+            // The elements were moved into the `HeaderVec`, so only the memory should be freed.
+            elements.set_len(0);
+        }
+        this
+    }
+
+    /// Creates a `HeaderVec` by concatenating `slices` with a clone of `sep` between each of them,
+    /// like [`slice::join`](https://doc.rust-lang.org/std/primitive.slice.html#method.join).
+    pub fn join_with(slices: &[&[T]], sep: &[T], head: H) -> Self
+    where
+        T: Clone,
+    {
+        let len = slices.iter().map(|slice| slice.len()).sum::<usize>()
+            + sep.len() * slices.len().saturating_sub(1);
+        let mut this = Self::with_capacity(len, head);
+        for (index, slice) in slices.iter().enumerate() {
+            if index != 0 {
+                for e in sep {
+                    this.push(e.clone());
+                }
+            }
+            for e in *slice {
+                this.push(e.clone());
+            }
+        }
+        this
+    }
+
+    /// Creates a `HeaderVec` whose elements start after at least `header_reserve_bytes` of padding
+    /// past the header. The padding is kept for the lifetime of the allocation, but it is only
+    /// padding: nothing reads or writes it, and the header type can't be changed in place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is a zero-sized type, since zero-sized elements are never stored after the header.
+    pub fn with_header_reserve(head: H, header_reserve_bytes: usize, capacity: usize) -> Self {
+        assert!(
+            Self::element_size_nonzero(),
+            "HeaderVec does not support zero-sized element types T"
+        );
+        // The offset is stored right after the header, and the requested padding comes after it.
+        let offset = mem::size_of::<HeaderVecHeader<H>>()
+            .checked_add(mem::size_of::<usize>())
+            .and_then(|bytes| bytes.checked_add(header_reserve_bytes))
+            .expect("HeaderVec header reserve overflow")
+            .div_ceil(mem::size_of::<T>());
+        Self::with_capacity_and_offset(capacity, offset, head, false)
+    }
+
+    /// Creates a `HeaderVec` that only ever grows by exactly as much as is needed, instead of
+    /// doubling its capacity. This avoids wasting memory for buffers that are written once and then read.
+    pub fn with_capacity_exact_growth(capacity: usize, head: H) -> Self {
+        let mut this = Self::with_capacity(capacity, head);
+        this.header_mut().capacity |= EXACT_GROWTH_FLAG;
+        this
+    }
+
+    /// Creates an empty `HeaderVec` with the same growth policy as `self`, so that clones of an
+    /// exact growth vector keep growing exactly.
+    fn with_capacity_like(&self, capacity: usize, head: H) -> Self {
+        let mut new_vec = Self::with_capacity(capacity, head);
+        new_vec.header_mut().capacity |= self.capacity_word() & EXACT_GROWTH_FLAG;
+        new_vec
+    }
+
+    /// Allocates the memory and writes the header. The elements are zeroed if `zeroed` is set.
+    fn with_capacity_and_offset(capacity: usize, offset: usize, head: H, zeroed: bool) -> Self {
+        Self::try_with_capacity_and_offset(capacity, offset, head, zeroed)
+            .unwrap_or_else(|error| Self::handle_reserve_error(error))
+    }
+
+    /// Same as [`HeaderVec::with_capacity_and_offset`], but returns an error instead of aborting
+    /// when the layout is too large or the allocator fails.
+    fn try_with_capacity_and_offset(
+        capacity: usize,
+        offset: usize,
+        head: H,
+        zeroed: bool,
+    ) -> Result<Self, TryReserveError> {
+        let capacity = cmp::max(capacity, Self::min_useful_capacity());
+        // Allocate the initial memory, which is unititialized unless it was asked to be zeroed.
+        let layout = Self::try_layout(offset, capacity)?;
+        let ptr = unsafe {
+            if zeroed {
+                alloc::alloc::alloc_zeroed(layout)
+            } else {
+                alloc::alloc::alloc(layout)
+            }
+        } as *mut T;
 
         // Handle out-of-memory.
         if ptr.is_null() {
-            alloc::alloc::handle_alloc_error(layout);
+            return Err(TryReserveError::AllocError { layout });
         }
 
         // Create self.
@@ -68,40 +427,213 @@ impl<H, T> HeaderVec<H, T> {
         };
 
         // Set the header.
-        let header = this.header_mut();
         // This makes sure to avoid the fact that the memory is initially uninitialized
         // and we don't want to trigger a call to drop() on uninitialized memory.
-        unsafe { core::ptr::write(&mut header.head, head) };
-        // These primitive types don't have drop implementations.
-        header.capacity = capacity;
-        header.len = 0;
+        // The whole header is written at once through the raw pointer, so that no reference
+        // to the uninitialized header is ever created.
+        unsafe {
+            core::ptr::write(
+                this.ptr as *mut HeaderVecHeader<H>,
+                HeaderVecHeader {
+                    head,
+                    capacity: capacity & CAPACITY_MASK,
+                    len: Default::default(),
+                    #[cfg(feature = "atomic_append")]
+                    version: AtomicUsize::new(0),
+                },
+            )
+        };
+        this.set_offset(offset);
 
-        this
+        Ok(this)
     }
 
+    /// Get the length of the vector.
+    ///
+    /// With the `atomic_append` feature this is a relaxed load, which is only guaranteed to be
+    /// up to date on the thread that appended the elements. It doesn't make the elements appended by
+    /// another thread visible, so don't use it to index into them. Use [`HeaderVec::len_strict`]
+    /// (which [`HeaderVec::as_slice`] uses) when the length must be synchronized with other threads.
+    #[must_use]
     #[inline(always)]
     pub fn len(&self) -> usize {
-        self.header().len
+        #[cfg(feature = "atomic_append")]
+        return unsafe { (*self.len_ptr()).load(Ordering::Relaxed) };
+        #[cfg(not(feature = "atomic_append"))]
+        return unsafe { *self.len_ptr() };
+    }
+
+    /// Get the length of the vector with acquire ordering, so that all elements appended by
+    /// other threads before the length was published are visible.
+    #[must_use]
+    #[inline(always)]
+    pub fn len_strict(&self) -> usize {
+        #[cfg(feature = "atomic_append")]
+        return unsafe { (*self.len_ptr()).load(Ordering::Acquire) };
+        #[cfg(not(feature = "atomic_append"))]
+        return unsafe { *self.len_ptr() };
+    }
+
+    /// Get the length of the vector through exclusive access, which needs no synchronization.
+    #[must_use]
+    #[inline(always)]
+    pub fn len_exact(&mut self) -> usize {
+        #[cfg(feature = "atomic_append")]
+        return *self.header_mut().len.get_mut();
+        #[cfg(not(feature = "atomic_append"))]
+        return self.header_mut().len;
+    }
+
+    /// Set the length of the vector through exclusive access.
+    #[inline(always)]
+    fn set_len_exact(&mut self, len: usize) {
+        #[cfg(feature = "atomic_append")]
+        {
+            *self.header_mut().len.get_mut() = len;
+        }
+        #[cfg(not(feature = "atomic_append"))]
+        {
+            self.header_mut().len = len;
+        }
     }
 
+    #[must_use]
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
+    #[must_use]
     #[inline(always)]
     pub fn capacity(&self) -> usize {
-        self.header().capacity
+        if !Self::element_size_nonzero() {
+            // Zero-sized elements never need to be reallocated.
+            return usize::MAX;
+        }
+        self.capacity_word() & CAPACITY_MASK
     }
 
+    #[must_use]
     #[inline(always)]
     pub fn as_slice(&self) -> &[T] {
-        unsafe { core::slice::from_raw_parts(self.start_ptr(), self.len()) }
+        unsafe { core::slice::from_raw_parts(self.start_ptr(), self.len_strict()) }
+    }
+
+    /// Returns the header and the elements together. This is the same as taking
+    /// `(&**self, self.as_slice())`, with the length read once with acquire ordering.
+    ///
+    /// This is not an atomic view of the header and the elements. With the `atomic_append` feature,
+    /// the header seen through the returned reference is only consistent with the slice when the
+    /// appending thread writes the header (for instance through atomics in `H`) before it publishes
+    /// the length. Anything it writes to the header afterwards may or may not be visible.
+    #[must_use]
+    #[inline(always)]
+    pub fn snapshot(&self) -> (&H, &[T]) {
+        (&self.header().head, self.as_slice())
     }
 
+    #[must_use]
     #[inline(always)]
     pub fn as_mut_slice(&mut self) -> &mut [T] {
-        unsafe { core::slice::from_raw_parts_mut(self.start_ptr_mut(), self.len()) }
+        unsafe { core::slice::from_raw_parts_mut(self.start_ptr_mut(), self.len_exact()) }
+    }
+
+    /// The number of elements that can be added without reallocating.
+    #[inline(always)]
+    pub fn spare_capacity(&self) -> usize {
+        self.capacity() - self.len_strict()
+    }
+
+    /// The number of bytes that can be added without reallocating.
+    #[inline(always)]
+    pub fn spare_capacity_bytes(&self) -> usize {
+        self.spare_capacity() * mem::size_of::<T>()
+    }
+
+    /// Returns a raw pointer to the first element.
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *const T {
+        self.start_ptr()
+    }
+
+    /// Returns a raw mutable pointer to the first element.
+    #[inline(always)]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.start_ptr_mut()
+    }
+
+    /// Returns the two raw pointers spanning the elements, like [`slice::as_ptr_range`].
+    #[inline(always)]
+    pub fn as_ptr_range(&self) -> Range<*const T> {
+        self.as_slice().as_ptr_range()
+    }
+
+    /// Returns a reference to the element at `index`, or an [`IndexError`] describing the
+    /// out-of-bounds access.
+    #[inline]
+    pub fn try_index(&self, index: usize) -> Result<&T, IndexError> {
+        let slice = self.as_slice();
+        slice.get(index).ok_or(IndexError {
+            index,
+            len: slice.len(),
+        })
+    }
+
+    /// Always returns `true`: the elements of a `HeaderVec` are stored contiguously, like an array,
+    /// and [`HeaderVec::as_ptr`] is always aligned to `align_of::<T>()`, including for
+    /// over-aligned types. This makes [`HeaderVec::as_slice`] suitable for SIMD loads with the
+    /// alignment of `T`.
+    ///
+    /// Note that the element region is only aligned beyond `align_of::<T>()` if `T` itself is.
+    #[inline(always)]
+    pub const fn elements_are_contiguous() -> bool {
+        true
+    }
+
+    /// Checks if `p` points into one of the elements of the vector.
+    #[inline(always)]
+    pub fn contains_ptr(&self, p: *const T) -> bool {
+        self.as_ptr_range().contains(&p)
+    }
+
+    /// The size in bytes of the memory allocated for the header and the element capacity.
+    #[inline(always)]
+    pub fn allocation_size(&self) -> usize {
+        Self::elems_to_mem_bytes(self.offset(), self.capacity())
+    }
+
+    /// The size in bytes that [`HeaderVec::with_capacity`] would allocate for the given capacity.
+    #[inline(always)]
+    pub fn bytes_for_capacity(capacity: usize) -> usize {
+        Self::elems_to_mem_bytes(Self::min_offset(), capacity)
+    }
+
+    /// Reports how the memory of the allocation is used.
+    pub fn memory_report(&self) -> MemoryReport {
+        let header_bytes = mem::size_of::<HeaderVecHeader<H>>();
+        let len = self.len_strict();
+        MemoryReport {
+            header_bytes,
+            padding_bytes: (self.offset() * mem::size_of::<T>()).saturating_sub(header_bytes),
+            element_bytes_used: len * mem::size_of::<T>(),
+            element_bytes_spare: (self.capacity() - len) * mem::size_of::<T>(),
+            total_bytes: self.allocation_size(),
+        }
+    }
+
+    /// Clones only the header, without touching the elements.
+    #[inline(always)]
+    pub fn clone_header(&self) -> H
+    where
+        H: Clone,
+    {
+        self.header().head.clone()
+    }
+
+    /// Projects the header to one of its parts, for instance a field.
+    #[inline(always)]
+    pub fn map_header_ref<R: ?Sized>(&self, f: impl FnOnce(&H) -> &R) -> &R {
+        f(&self.header().head)
     }
 
     /// This is useful to check if two nodes are the same. Use it with [`HeaderVec::is`].
@@ -110,6 +642,19 @@ impl<H, T> HeaderVec<H, T> {
         self.ptr as *const ()
     }
 
+    /// Returns the base pointer, the length and the capacity, for logging and debugging.
+    /// With the `atomic_append` feature, the length is read with acquire ordering.
+    #[inline(always)]
+    pub fn debug_raw_parts(&self) -> (*const (), usize, usize) {
+        (self.ptr(), self.len_strict(), self.capacity())
+    }
+
+    /// Same as [`HeaderVec::ptr`], but the pointer is never null since the header is always allocated.
+    #[inline(always)]
+    pub fn as_non_null(&self) -> NonNull<()> {
+        unsafe { NonNull::new_unchecked(self.ptr as *mut ()) }
+    }
+
     /// This is used to check if this is the `HeaderVec` that corresponds to the given pointer.
     /// This is useful for updating weak references after [`HeaderVec::push`] returns the pointer.
     #[inline(always)]
@@ -142,6 +687,7 @@ impl<H, T> HeaderVec<H, T> {
                 ptr: self.ptr,
                 _phantom: PhantomData,
             }),
+            _not_send_sync: PhantomData,
         }
     }
 
@@ -156,24 +702,132 @@ impl<H, T> HeaderVec<H, T> {
         self.ptr = weak.ptr;
     }
 
+    /// Extends the vector with the items of `iter`, and updates `weak` to point to the new memory
+    /// location if the vector reallocated.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location, so that any other
+    /// weak references can be updated as well.
+    ///
+    /// # Safety
+    ///
+    /// `weak` must be a weak reference to this `HeaderVec`. See the safety section in [`HeaderVec::weak`].
+    pub unsafe fn extend_updating_weak<I: IntoIterator<Item = T>>(
+        &mut self,
+        weak: &mut HeaderVecWeak<H, T>,
+        iter: I,
+    ) -> Option<*const ()> {
+        let old_ptr = self.ptr();
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.push(item);
+        }
+        if self.is(old_ptr) {
+            None
+        } else {
+            weak.update(self.weak());
+            Some(old_ptr)
+        }
+    }
+
+    /// Clones the `HeaderVec` that a weak reference points to. The clone is completely independent
+    /// of the original, so it doesn't need to be updated when the original reallocates.
+    ///
+    /// # Safety
+    ///
+    /// The `HeaderVec` that `weak` was created from must still be alive, and `weak` must have been
+    /// updated after every reallocation. See the safety section in [`HeaderVec::weak`].
+    pub unsafe fn clone_from_weak(weak: &HeaderVecWeak<H, T>) -> HeaderVec<H, T>
+    where
+        H: Clone,
+        T: Clone,
+    {
+        HeaderVec::clone(weak)
+    }
+
     #[cold]
     fn resize_insert(&mut self) -> Option<*const ()> {
         let old_capacity = self.capacity();
-        let new_capacity = old_capacity * 2;
-        // Set the new capacity.
-        self.header_mut().capacity = new_capacity;
+        let new_capacity = Self::next_capacity(old_capacity, old_capacity + 1, self.exact_growth());
+        self.resize_cold(new_capacity)
+    }
+
+    /// The capacity the vector grows to when it holds `current` elements and needs room for
+    /// `requested` elements. With `exact` growth (see [`HeaderVec::with_capacity_exact_growth`]),
+    /// this is exactly `requested`, otherwise the capacity at least doubles.
+    ///
+    /// Returns `current` if it is already enough.
+    #[inline]
+    pub fn next_capacity(current: usize, requested: usize, exact: bool) -> usize {
+        if requested <= current {
+            current
+        } else if exact {
+            requested
+        } else {
+            cmp::max(requested, current.saturating_mul(2))
+        }
+    }
+
+    /// Reallocates the memory so that it holds exactly `new_capacity` elements.
+    #[cold]
+    fn resize_cold(&mut self, new_capacity: usize) -> Option<*const ()> {
+        let offset = self.offset();
+        self.realloc_cold(offset, offset, new_capacity)
+    }
+
+    /// Reallocates the memory so that the elements start at `new_offset` and there is room for
+    /// exactly `new_capacity` elements. The elements must already be located at `new_offset`, and
+    /// `old_offset` is where they started when the memory was allocated.
+    #[cold]
+    fn realloc_cold(
+        &mut self,
+        old_offset: usize,
+        new_offset: usize,
+        new_capacity: usize,
+    ) -> Option<*const ()> {
+        self.try_realloc_cold(old_offset, new_offset, new_capacity)
+            .unwrap_or_else(|error| Self::handle_reserve_error(error))
+    }
+
+    /// Panics if the capacity is too large, or aborts through
+    /// [`handle_alloc_error`](alloc::alloc::handle_alloc_error) if the allocator failed.
+    #[cold]
+    fn handle_reserve_error(error: TryReserveError) -> ! {
+        match error {
+            TryReserveError::CapacityOverflow => {
+                panic!("unable to produce memory layout for HeaderVec (is the capacity too large?)")
+            }
+            TryReserveError::AllocError { layout } => alloc::alloc::handle_alloc_error(layout),
+        }
+    }
+
+    /// Same as [`HeaderVec::realloc_cold`], but returns an error instead of aborting when the
+    /// layout is too large or the allocator fails. The vector is left untouched in that case.
+    #[cold]
+    fn try_realloc_cold(
+        &mut self,
+        old_offset: usize,
+        new_offset: usize,
+        new_capacity: usize,
+    ) -> Result<Option<*const ()>, TryReserveError> {
+        let old_capacity = self.capacity();
+        let old_layout = Self::layout(old_offset, old_capacity);
+        let new_layout = Self::try_layout(new_offset, new_capacity)?;
         // Reallocate the pointer.
         let ptr = unsafe {
-            alloc::alloc::realloc(
-                self.ptr as *mut u8,
-                Self::layout(old_capacity),
-                Self::elems_to_mem_bytes(new_capacity),
-            ) as *mut T
+            alloc::alloc::realloc(self.ptr as *mut u8, old_layout, new_layout.size()) as *mut T
         };
         // Handle out-of-memory.
         if ptr.is_null() {
-            alloc::alloc::handle_alloc_error(Self::layout(new_capacity));
+            return Err(TryReserveError::AllocError { layout: new_layout });
         }
+        #[cfg(feature = "realloc_trace")]
+        log::trace!(
+            target: "header_vec",
+            "HeaderVec reallocated from capacity {} to {}",
+            old_capacity,
+            new_capacity
+        );
         // Check if the new pointer is different than the old one.
         let previous_pointer = if ptr != self.ptr {
             // Give the user the old pointer so they can update everything.
@@ -183,8 +837,137 @@ impl<H, T> HeaderVec<H, T> {
         };
         // Assign the new pointer.
         self.ptr = ptr;
+        // Set the new offset and capacity, keeping the flags.
+        let header = self.header_mut();
+        header.capacity = (header.capacity & !CAPACITY_MASK) | (new_capacity & CAPACITY_MASK);
+        self.set_offset(new_offset);
 
-        previous_pointer
+        Ok(previous_pointer)
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn reserve(&mut self, additional: usize) -> Option<*const ()> {
+        match self.reserve_detailed(additional) {
+            ReserveOutcome::Moved { old } => Some(old),
+            ReserveOutcome::NoGrowthNeeded | ReserveOutcome::GrewInPlace => None,
+        }
+    }
+
+    /// Reserves capacity for exactly `additional` more elements, without growing any further.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn reserve_exact(&mut self, additional: usize) -> Option<*const ()> {
+        let required = self
+            .len_exact()
+            .checked_add(additional)
+            .expect("HeaderVec capacity overflow");
+        if required <= self.capacity() {
+            return None;
+        }
+        self.resize_cold(required)
+    }
+
+    /// Same as [`HeaderVec::reserve`], but also tells apart whether the vector had to grow when
+    /// the memory stayed in the same location.
+    pub fn reserve_detailed(&mut self, additional: usize) -> ReserveOutcome {
+        let required = self
+            .len_exact()
+            .checked_add(additional)
+            .expect("HeaderVec capacity overflow");
+        let old_capacity = self.capacity();
+        if required <= old_capacity {
+            return ReserveOutcome::NoGrowthNeeded;
+        }
+        let new_capacity = Self::next_capacity(old_capacity, required, self.exact_growth());
+        match self.resize_cold(new_capacity) {
+            Some(old) => ReserveOutcome::Moved { old },
+            None => ReserveOutcome::GrewInPlace,
+        }
+    }
+
+    /// Grows or shrinks the capacity to exactly `new_capacity`.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_capacity` is less than the length or is 0.
+    pub fn set_capacity(&mut self, new_capacity: usize) -> Option<*const ()> {
+        assert!(
+            new_capacity >= self.len_exact(),
+            "HeaderVec capacity cannot be less than its length"
+        );
+        assert!(new_capacity > 0, "HeaderVec capacity cannot be 0");
+        // Zero-sized elements never need more memory, so there is nothing to resize.
+        if !Self::element_size_nonzero() || new_capacity == self.capacity() {
+            return None;
+        }
+        self.resize_cold(new_capacity)
+    }
+
+    /// Reallocates the capacity to exactly the length plus `additional`, which both reclaims
+    /// unused capacity and reserves room for more elements in a single reallocation.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn compact_and_reserve(&mut self, additional: usize) -> Option<*const ()> {
+        let new_capacity = self
+            .len_exact()
+            .checked_add(additional)
+            .expect("HeaderVec capacity overflow");
+        self.set_capacity(cmp::max(new_capacity, 1))
+    }
+
+    /// Shrinks the capacity to the length like [`HeaderVec::shrink_to_fit`], and also removes any
+    /// padding reserved after the header with [`HeaderVec::with_header_reserve`].
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn compact_fully(&mut self) -> Option<*const ()> {
+        if !Self::element_size_nonzero() {
+            return None;
+        }
+        let len = self.len_exact();
+        let old_offset = self.offset();
+        let new_offset = Self::min_offset();
+        let new_capacity = cmp::max(len, 1);
+        if new_offset == old_offset && new_capacity == self.capacity() {
+            return None;
+        }
+        if new_offset != old_offset {
+            // Move the elements down next to the header before the allocation shrinks.
+            unsafe { ptr::copy(self.ptr.add(old_offset), self.ptr.add(new_offset), len) };
+        }
+        self.realloc_cold(old_offset, new_offset, new_capacity)
+    }
+
+    /// Shrinks the capacity of the vector as much as possible, while keeping room for at least one element.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    ///
+    /// With the `atomic_append` feature, this takes `&mut self` so no producer can be appending
+    /// concurrently, and the length is read through exclusive access so that it reflects every
+    /// prior atomic append. Shrinking to a stale length would drop live elements out of the allocation.
+    pub fn shrink_to_fit(&mut self) -> Option<*const ()> {
+        let new_capacity = cmp::max(self.len_exact(), 1);
+        if !Self::element_size_nonzero() || new_capacity == self.capacity() {
+            return None;
+        }
+        self.resize_cold(new_capacity)
+    }
+
+    /// Same as [`HeaderVec::shrink_to_fit`], but also returns how many bytes of the allocation
+    /// were given back to the allocator.
+    pub fn shrink_to_fit_reporting(&mut self) -> (Option<*const ()>, usize) {
+        let old_size = self.allocation_size();
+        let previous_pointer = self.shrink_to_fit();
+        (previous_pointer, old_size - self.allocation_size())
     }
 
     /// Adds an item to the end of the list.
@@ -192,7 +975,7 @@ impl<H, T> HeaderVec<H, T> {
     /// Returns `true` if the memory was moved to a new location.
     /// In this case, you are responsible for updating the weak nodes.
     pub fn push(&mut self, item: T) -> Option<*const ()> {
-        let old_len = self.len();
+        let old_len = self.len_exact();
         let new_len = old_len + 1;
         let old_capacity = self.capacity();
         // If it isn't big enough.
@@ -201,27 +984,235 @@ impl<H, T> HeaderVec<H, T> {
         } else {
             None
         };
+        // The element pointer is derived from the raw `self.ptr` after any reallocation, and the
+        // header is only borrowed briefly afterwards to store the length. The header reference
+        // covers only the header bytes, so it never invalidates pointers into the element region.
         unsafe {
             core::ptr::write(self.start_ptr_mut().add(old_len), item);
         }
-        self.header_mut().len = new_len;
+        self.set_len_exact(new_len);
         previous_pointer
     }
 
-    /// Retains only the elements specified by the predicate.
+    /// Adds an item to the end of the list, and when the vector is full, reserves room for at
+    /// least `hint` more items at once instead of only growing for this one. Pass the number of
+    /// items still to come in a batch so the loop doesn't need to grow again.
     ///
-    /// In other words, remove all elements `e` such that `f(&e)` returns `false`.
-    /// This method operates in place, visiting each element exactly once in the original order,
-    /// and preserves the order of the retained elements.
-    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
-        // This keeps track of the length (and next position) of the contiguous retained elements
-        // at the beginning of the vector.
-        let mut head = 0;
-        let original_len = self.len();
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    #[inline]
+    pub fn push_reserving(&mut self, item: T, hint: usize) -> Option<*const ()> {
+        let old_len = self.len_exact();
+        let previous_pointer = if old_len == self.capacity() {
+            self.reserve(cmp::max(hint, 1))
+        } else {
+            None
+        };
+        unsafe {
+            ptr::write(self.start_ptr_mut().add(old_len), item);
+        }
+        self.set_len_exact(old_len + 1);
+        previous_pointer
+    }
+
+    /// Moves all the elements of an array to the end of the list, reserving room for them at once.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn push_array<const N: usize>(&mut self, items: [T; N]) -> Option<*const ()> {
+        let previous_pointer = self.reserve(N);
+        let old_len = self.len_exact();
+        let items = ManuallyDrop::new(items);
+        unsafe {
+            ptr::copy_nonoverlapping(items.as_ptr(), self.start_ptr_mut().add(old_len), N);
+        }
+        self.set_len_exact(old_len + N);
+        previous_pointer
+    }
+
+    /// Removes the last element and returns it, or `None` if the vector is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let len = self.len_exact().checked_sub(1)?;
+        self.set_len_exact(len);
+        Some(unsafe { ptr::read(self.start_ptr().add(len)) })
+    }
+
+    /// Removes the element at `index` and returns it, replacing it with the last element.
+    ///
+    /// This doesn't preserve the order of the elements, but is O(1).
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.len_exact();
+        assert!(
+            index < len,
+            "swap_remove index (is {index}) should be < len (is {len})"
+        );
+        let start_ptr = self.start_ptr_mut();
+        unsafe {
+            let item = ptr::read(start_ptr.add(index));
+            ptr::copy(start_ptr.add(len - 1), start_ptr.add(index), 1);
+            self.set_len_exact(len - 1);
+            item
+        }
+    }
+
+    /// Removes the first element and returns it, replacing it with the last element, or returns
+    /// `None` if the vector is empty. This is an O(1) way to take elements from the front when their
+    /// order doesn't matter.
+    #[inline]
+    pub fn swap_remove_front(&mut self) -> Option<T> {
+        if self.len_exact() == 0 {
+            None
+        } else {
+            Some(self.swap_remove(0))
+        }
+    }
+
+    /// Removes the first element and returns it, shifting all the other elements to the left, or
+    /// returns `None` if the vector is empty.
+    ///
+    /// This keeps the order of the elements, but is O(n). Use [`HeaderVec::swap_remove_front`] when
+    /// the order doesn't matter.
+    pub fn pop_front(&mut self) -> Option<T> {
+        let len = self.len_exact().checked_sub(1)?;
+        let start_ptr = self.start_ptr_mut();
+        unsafe {
+            let item = ptr::read(start_ptr);
+            ptr::copy(start_ptr.add(1), start_ptr, len);
+            self.set_len_exact(len);
+            Some(item)
+        }
+    }
+
+    /// Shortens the vector, keeping the first `len` elements and dropping the rest.
+    ///
+    /// If `len` is greater or equal to the vector's current length, this has no effect.
+    /// When `T` doesn't need to be dropped, this only sets the length, so it takes constant time.
+    ///
+    /// With the `atomic_append` feature, taking `&mut self` guarantees that no other thread is
+    /// appending or reading at the same time. Whatever hands the vector back to other threads (a
+    /// mutex, a channel or the end of a scoped thread) makes the shorter length visible to them, so
+    /// the length is stored through exclusive access without any atomic ordering.
+    pub fn truncate(&mut self, len: usize) {
+        let old_len = self.len_exact();
+        if len >= old_len {
+            return;
+        }
+        // Set the length first, so that a panic while dropping can't cause a double drop.
+        self.set_len_exact(len);
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.start_ptr_mut().add(len),
+                old_len - len,
+            ));
+        }
+    }
+
+    /// Removes all the elements, keeping the capacity.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Removes all the elements and resets the header to its default value, keeping the capacity.
+    /// This is useful for recycling vectors in an object pool.
+    pub fn clear_and_reset_header(&mut self)
+    where
+        H: Default,
+    {
+        self.clear();
+        self.header_mut().head = H::default();
+    }
+
+    /// Same as [`HeaderVec::truncate`], but the removed elements are returned instead of dropped.
+    pub fn truncate_returning(&mut self, len: usize) -> Vec<T> {
+        let len = cmp::min(len, self.len_exact());
+        self.drain(len..).collect()
+    }
+
+    /// Same as [`slice::rotate_left`] on the elements, but when the `mid` elements being moved to the
+    /// back fit in a small stack buffer, they are set aside and the rest is moved with a single
+    /// `memmove`.
+    ///
+    /// Panics if `mid` is greater than the length.
+    pub fn rotate_left_copy(&mut self, mid: usize)
+    where
+        T: Copy,
+    {
+        let len = self.len_exact();
+        assert!(mid <= len, "mid is greater than the length");
+        let Some(buffer_len) = Self::rotate_buffer_len(mid) else {
+            self.as_mut_slice().rotate_left(mid);
+            return;
+        };
+        let mut buffer = [MaybeUninit::<u8>::uninit(); ROTATE_BUFFER_BYTES];
+        let buffer = buffer.as_mut_ptr() as *mut u8;
+        let start = self.start_ptr_mut();
+        unsafe {
+            ptr::copy_nonoverlapping(start as *const u8, buffer, buffer_len);
+            ptr::copy(start.add(mid), start, len - mid);
+            ptr::copy_nonoverlapping(buffer, start.add(len - mid) as *mut u8, buffer_len);
+        }
+    }
+
+    /// Same as [`slice::rotate_right`] on the elements, but when the `k` elements being moved to the
+    /// front fit in a small stack buffer, they are set aside and the rest is moved with a single
+    /// `memmove`.
+    ///
+    /// Panics if `k` is greater than the length.
+    pub fn rotate_right_copy(&mut self, k: usize)
+    where
+        T: Copy,
+    {
+        let len = self.len_exact();
+        assert!(k <= len, "k is greater than the length");
+        let Some(buffer_len) = Self::rotate_buffer_len(k) else {
+            self.as_mut_slice().rotate_right(k);
+            return;
+        };
+        let mut buffer = [MaybeUninit::<u8>::uninit(); ROTATE_BUFFER_BYTES];
+        let buffer = buffer.as_mut_ptr() as *mut u8;
+        let start = self.start_ptr_mut();
+        unsafe {
+            ptr::copy_nonoverlapping(start.add(len - k) as *const u8, buffer, buffer_len);
+            ptr::copy(start, start.add(k), len - k);
+            ptr::copy_nonoverlapping(buffer, start as *mut u8, buffer_len);
+        }
+    }
+
+    /// The number of bytes taken by `count` elements if they fit in the rotate buffer.
+    #[inline(always)]
+    fn rotate_buffer_len(count: usize) -> Option<usize> {
+        count
+            .checked_mul(mem::size_of::<T>())
+            .filter(|&bytes| bytes <= ROTATE_BUFFER_BYTES)
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// In other words, remove all elements `e` such that `f(&e)` returns `false`.
+    /// This method operates in place, visiting each element exactly once in the original order,
+    /// and preserves the order of the retained elements.
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let original_len = self.len_exact();
         // Get the offset of the beginning of the slice.
         let start_ptr = self.start_ptr_mut();
-        // Go through each index.
-        for index in 0..original_len {
+        // Skip over the elements that are kept before the first removal, since they don't move.
+        // When nothing is removed, the vector is left untouched.
+        let Some(first_removed) =
+            (0..original_len).find(|&index| !f(unsafe { &*start_ptr.add(index) }))
+        else {
+            return;
+        };
+        // If `f` panics from here on, the elements are leaked rather than dropped twice.
+        self.set_len_exact(0);
+        unsafe { ptr::drop_in_place(start_ptr.add(first_removed)) };
+        // This keeps track of the length (and next position) of the contiguous retained elements
+        // at the beginning of the vector.
+        let mut head = first_removed;
+        // Go through each remaining index.
+        for index in first_removed + 1..original_len {
             unsafe {
                 // Call the retain function on the derefed pointer to each index.
                 if f(&*start_ptr.add(index)) {
@@ -239,61 +1230,1033 @@ impl<H, T> HeaderVec<H, T> {
             }
         }
         // The head now represents the new length of the vector.
-        self.header_mut().len = head;
+        self.set_len_exact(head);
+    }
+
+    /// Removes consecutive repeated elements, like [`Vec::dedup`].
+    #[inline]
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_counting();
+    }
+
+    /// Same as [`HeaderVec::dedup`], but returns how many elements were removed.
+    pub fn dedup_counting(&mut self) -> usize
+    where
+        T: PartialEq,
+    {
+        let original_len = self.len_exact();
+        if original_len < 2 {
+            return 0;
+        }
+        let start_ptr = self.start_ptr_mut();
+        // If a comparison panics, the elements are leaked rather than dropped twice.
+        self.set_len_exact(0);
+        // The first element is always kept.
+        let mut head = 1;
+        for index in 1..original_len {
+            unsafe {
+                if *start_ptr.add(index) == *start_ptr.add(head - 1) {
+                    ptr::drop_in_place(start_ptr.add(index));
+                } else {
+                    if head != index {
+                        ptr::copy_nonoverlapping(start_ptr.add(index), start_ptr.add(head), 1);
+                    }
+                    head += 1;
+                }
+            }
+        }
+        self.set_len_exact(head);
+        original_len - head
+    }
+
+    /// Retains only the elements specified by the predicate, giving it mutable access to both the
+    /// header and each element.
+    ///
+    /// This behaves like [`HeaderVec::retain`], but `f(&mut header, &mut e)` can also update the
+    /// header (for instance to count the removed elements) and modify the retained elements.
+    pub fn retain_mut_with_header(&mut self, mut f: impl FnMut(&mut H, &mut T) -> bool) {
+        let mut head = 0;
+        let original_len = self.len_exact();
+        let start_ptr = self.start_ptr_mut();
+        // If `f` panics, the elements are leaked rather than dropped twice.
+        self.set_len_exact(0);
+        // The header and the elements are in separate regions of the allocation, so a mutable
+        // reference to the header doesn't alias any of the elements.
+        let header = unsafe { &mut (*(self.ptr as *mut HeaderVecHeader<H>)).head };
+        for index in 0..original_len {
+            unsafe {
+                if f(header, &mut *start_ptr.add(index)) {
+                    if head != index {
+                        ptr::copy_nonoverlapping(start_ptr.add(index), start_ptr.add(head), 1);
+                    }
+                    head += 1;
+                } else {
+                    ptr::drop_in_place(start_ptr.add(index));
+                }
+            }
+        }
+        self.set_len_exact(head);
+    }
+
+    /// Creates a new `HeaderVec` with a clone of the header and the elements repeated `n` times,
+    /// like [`slice::repeat`](https://doc.rust-lang.org/std/primitive.slice.html#method.repeat).
+    pub fn repeat(&self, n: usize) -> Self
+    where
+        H: Clone,
+        T: Clone,
+    {
+        let len = self
+            .len_strict()
+            .checked_mul(n)
+            .expect("HeaderVec capacity overflow");
+        let mut new_vec = Self::with_capacity(cmp::max(len, 1), self.clone_header());
+        for _ in 0..n {
+            for e in self.as_slice() {
+                new_vec.push(e.clone());
+            }
+        }
+        new_vec
+    }
+
+    /// Swaps the headers of two vectors, leaving the elements of both untouched.
+    #[inline(always)]
+    pub fn swap_header_with(&mut self, other: &mut HeaderVec<H, T>) {
+        mem::swap(&mut self.header_mut().head, &mut other.header_mut().head);
+    }
+
+    /// Creates an iterator which uses a closure to determine if an element should be removed.
+    ///
+    /// If the closure returns `true`, the element is removed from the vector and yielded. If it
+    /// returns `false`, the element remains in the vector. The retained elements keep their order.
+    ///
+    /// If the iterator is dropped before it is fully consumed, the elements that were not visited
+    /// yet are kept in the vector.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, H, T, F>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let old_len = self.len_exact();
+        // If the iterator is leaked, the elements are leaked instead of being exposed twice.
+        self.set_len_exact(0);
+        ExtractIf {
+            vec: self,
+            index: 0,
+            deleted: 0,
+            old_len,
+            pred,
+        }
+    }
+
+    /// Returns an iterator over every `step`-th element, starting with the first one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is 0.
+    #[inline(always)]
+    pub fn stride(&self, step: usize) -> impl Iterator<Item = &T> {
+        self.as_slice().iter().step_by(step)
+    }
+
+    /// Returns mutable references to the header and to the elements at the same time.
+    #[inline(always)]
+    pub fn header_and_slice_mut(&mut self) -> (&mut H, &mut [T]) {
+        let len = self.len_exact();
+        let start_ptr = self.start_ptr_mut();
+        // The header and the elements are in separate regions of the allocation, so the two
+        // references never overlap.
+        unsafe {
+            (
+                &mut (*(self.ptr as *mut HeaderVecHeader<H>)).head,
+                core::slice::from_raw_parts_mut(start_ptr, len),
+            )
+        }
+    }
+
+    /// Returns a mutable reference to the header together with a mutable iterator over the elements.
+    #[inline(always)]
+    pub fn header_and_iter_mut(&mut self) -> (&mut H, core::slice::IterMut<'_, T>) {
+        let (head, slice) = self.header_and_slice_mut();
+        (head, slice.iter_mut())
+    }
+
+    /// Iterates over every pair of adjacent elements, `(e[i], e[i + 1])`.
+    #[inline]
+    pub fn pairs(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.as_slice()
+            .windows(2)
+            .map(|window| (&window[0], &window[1]))
+    }
+
+    /// Checks if the elements are sorted, see [`slice::is_sorted`].
+    #[inline(always)]
+    pub fn is_sorted(&self) -> bool
+    where
+        T: PartialOrd,
+    {
+        self.as_slice().is_sorted()
+    }
+
+    /// Checks if the elements are sorted using the given comparator, see [`slice::is_sorted_by`].
+    #[inline(always)]
+    pub fn is_sorted_by(&self, compare: impl FnMut(&T, &T) -> bool) -> bool {
+        self.as_slice().is_sorted_by(compare)
+    }
+
+    /// Same as [`HeaderVec::retain`], but afterwards the capacity is shrunk to twice the length
+    /// if less than a quarter of it is in use.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn retain_and_maybe_shrink(&mut self, f: impl FnMut(&T) -> bool) -> Option<*const ()> {
+        self.retain(f);
+        let len = self.len_exact();
+        if len < self.capacity() / 4 {
+            self.resize_cold(cmp::max(len * 2, 1))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if `T` is not a zero-sized type. Zero-sized elements have no storage, so their
+    /// slice is backed by a dangling pointer and the capacity is unlimited.
+    #[inline(always)]
+    pub const fn element_size_nonzero() -> bool {
+        mem::size_of::<T>() != 0
+    }
+
+    /// Calls `f` on each element, along with a shared reference to the header.
+    #[inline(always)]
+    pub fn for_each_mut_with_header(&mut self, mut f: impl FnMut(&H, &mut T)) {
+        let (head, slice) = self.header_and_slice_mut();
+        for e in slice {
+            f(head, e);
+        }
+    }
+
+    /// Clones the vector with room for `extra` more elements, so that they can be pushed onto the
+    /// clone without reallocating (and thus without invalidating its weak references).
+    pub fn clone_with_extra_capacity(&self, extra: usize) -> Self
+    where
+        H: Clone,
+        T: Clone,
+    {
+        let capacity = self
+            .len_strict()
+            .checked_add(extra)
+            .expect("HeaderVec capacity overflow");
+        let mut new_vec = self.with_capacity_like(cmp::max(capacity, 1), self.clone_header());
+        for e in self.as_slice() {
+            new_vec.push(e.clone());
+        }
+        new_vec
+    }
+
+    /// Swaps the element at index `i` of this vector with the element at index `j` of `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    #[inline(always)]
+    pub fn swap_element_with(&mut self, i: usize, other: &mut HeaderVec<H, T>, j: usize) {
+        // Two distinct `&mut` references can't point to the same vector, so the elements never overlap.
+        mem::swap(&mut self.as_mut_slice()[i], &mut other.as_mut_slice()[j]);
+    }
+
+    /// Splits the vector into vectors of at most `chunk_size` elements each, with a clone of the
+    /// header in every one of them. The last chunk may be smaller.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn into_chunks(mut self, chunk_size: usize) -> Vec<HeaderVec<H, T>>
+    where
+        H: Clone,
+    {
+        assert!(chunk_size > 0, "HeaderVec chunk size cannot be 0");
+        let len = self.len_exact();
+        // The elements are moved out, so the vector must no longer drop them.
+        self.set_len_exact(0);
+        let start_ptr = self.start_ptr_mut();
+        let mut chunks = Vec::with_capacity(len.div_ceil(chunk_size));
+        for chunk_start in (0..len).step_by(chunk_size) {
+            let chunk_len = cmp::min(chunk_size, len - chunk_start);
+            let mut chunk = Self::with_capacity(chunk_len, self.clone_header());
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    start_ptr.add(chunk_start),
+                    chunk.start_ptr_mut(),
+                    chunk_len,
+                );
+            }
+            chunk.set_len_exact(chunk_len);
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    /// Removes the elements in `range` from the vector and returns them as an iterator.
+    ///
+    /// When the iterator is dropped, any elements in the range that were not consumed are dropped
+    /// and the elements after the range are moved back to close the gap. The capacity is kept, so
+    /// call [`HeaderVec::shrink_to_fit`] afterwards to return the unused memory to the allocator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is greater than its end.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, H, T> {
+        self.drain_with(range, false)
+    }
+
+    /// Same as [`HeaderVec::drain`], but when `shrink` is set, the vector is shrunk to fit the
+    /// remaining elements once the iterator is dropped.
+    ///
+    /// Shrinking can move the memory to a new location, and the `Drain` has no way to return the
+    /// previous pointer. Check [`HeaderVec::is`] against the pointer you had before draining to
+    /// know whether the weak nodes need to be updated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is greater than its end.
+    pub fn drain_with<R: RangeBounds<usize>>(&mut self, range: R, shrink: bool) -> Drain<'_, H, T> {
+        let len = self.len_exact();
+        let Range { start, end } = slice_range(range, len);
+        // If the iterator is leaked, the elements after the start are leaked instead of being exposed twice.
+        self.set_len_exact(start);
+        Drain {
+            vec: self,
+            index: start,
+            end,
+            tail_start: end,
+            tail_len: len - end,
+            shrink,
+        }
+    }
+
+    /// Returns the elements as an array reference if there are exactly `N` of them.
+    #[inline(always)]
+    pub fn as_array<const N: usize>(&self) -> Option<&[T; N]> {
+        self.as_slice().try_into().ok()
+    }
+
+    /// Moves the elements into an array and returns it together with the header, freeing the
+    /// allocation. If there aren't exactly `N` elements, the vector is given back unchanged.
+    pub fn into_array<const N: usize>(mut self) -> Result<([T; N], H), Self> {
+        if self.len_exact() != N {
+            return Err(self);
+        }
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            let array = ptr::read(this.start_ptr() as *const [T; N]);
+            let head = ptr::read(&this.header().head);
+            alloc::alloc::dealloc(
+                this.ptr as *mut u8,
+                Self::layout(this.offset(), this.capacity()),
+            );
+            Ok((array, head))
+        }
+    }
+
+    /// Splits the vector into its header and a `Vec` holding the elements, freeing the allocation.
+    pub fn into_header_and_vec(mut self) -> (H, Vec<T>) {
+        let len = self.len_exact();
+        let mut elements = Vec::with_capacity(len);
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            ptr::copy_nonoverlapping(this.start_ptr(), elements.as_mut_ptr(), len);
+            elements.set_len(len);
+            let head = ptr::read(&this.header().head);
+            alloc::alloc::dealloc(
+                this.ptr as *mut u8,
+                Self::layout(this.offset(), this.capacity()),
+            );
+            (head, elements)
+        }
+    }
+
+    /// Overwrites every element with a clone of the element at the same index in `src`, dropping
+    /// the old values, like [`slice::clone_from_slice`].
+    ///
+    /// Panics if `src` doesn't have the same length as the vector.
+    pub fn clone_from_slice(&mut self, src: &[T])
+    where
+        T: Clone,
+    {
+        self.as_mut_slice().clone_from_slice(src);
+    }
+
+    /// Compares only the headers of two vectors.
+    #[inline(always)]
+    pub fn header_eq(&self, other: &Self) -> bool
+    where
+        H: PartialEq,
+    {
+        self.header().head == other.header().head
+    }
+
+    /// Compares only the elements of two vectors.
+    #[inline(always)]
+    pub fn elements_eq(&self, other: &Self) -> bool
+    where
+        T: PartialEq,
+    {
+        self.as_slice() == other.as_slice()
+    }
+
+    /// Returns an iterator over raw pointers to each element. The pointers stay valid until the
+    /// vector reallocates or the elements are removed.
+    #[inline(always)]
+    pub fn iter_element_ptrs(&self) -> impl Iterator<Item = *const T> {
+        let start_ptr = self.start_ptr();
+        (0..self.len_strict()).map(move |index| unsafe { start_ptr.add(index) })
+    }
+
+    /// Appends clones of every element in `slice`.
+    ///
+    /// `slice` must not borrow from this vector, for instance through a [`HeaderVecWeak`], since
+    /// reserving can free the memory it points to. Use [`HeaderVec::extend_from_within`] to append
+    /// elements of the vector itself.
+    ///
+    /// Unlike the `_atomic` appends, this can grow the vector. With the `atomic_append` feature, it
+    /// can be mixed with [`HeaderVec::try_push_atomic`]: the length is updated through exclusive
+    /// access, and whatever gives up that access makes the new elements visible to other threads.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn extend_from_slice(&mut self, slice: &[T]) -> Option<*const ()>
+    where
+        T: Clone,
+    {
+        let previous_pointer = self.reserve(slice.len());
+        let len = self.len_exact();
+        let dst = self.start_ptr_mut();
+        for (index, item) in slice.iter().enumerate() {
+            unsafe { ptr::write(dst.add(len + index), item.clone()) };
+            // The length is updated after each element, so a panicking clone doesn't leak or double drop.
+            self.set_len_exact(len + index + 1);
+        }
+        previous_pointer
+    }
+
+    /// Appends clones of the elements in `range` to the end of the vector, like
+    /// [`Vec::extend_from_within`].
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is greater than its end.
+    pub fn extend_from_within<R: RangeBounds<usize>>(&mut self, range: R) -> Option<*const ()>
+    where
+        T: Clone,
+    {
+        let len = self.len_exact();
+        let Range { start, end } = slice_range(range, len);
+        let previous_pointer = self.reserve(end - start);
+        // The source is only located after reserving, so it is read from the current allocation.
+        let start_ptr = self.start_ptr_mut();
+        for (index, source) in (start..end).enumerate() {
+            unsafe {
+                let item = (*start_ptr.add(source)).clone();
+                ptr::write(start_ptr.add(len + index), item);
+            }
+            // The length is updated after each element, so a panicking clone doesn't leak or double drop.
+            self.set_len_exact(len + index + 1);
+        }
+        previous_pointer
+    }
+
+    /// Appends a copy of every element in `slice` with a single reserve and copy.
+    fn extend_from_slice_copy(&mut self, slice: &[T]) -> Option<*const ()>
+    where
+        T: Copy,
+    {
+        let previous_pointer = self.reserve(slice.len());
+        let old_len = self.len_exact();
+        unsafe {
+            ptr::copy_nonoverlapping(
+                slice.as_ptr(),
+                self.start_ptr_mut().add(old_len),
+                slice.len(),
+            );
+        }
+        self.set_len_exact(old_len + slice.len());
+        previous_pointer
+    }
+
+    /// Creates a new vector with a clone of the header and clones of the elements in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is greater than its end.
+    pub fn clone_range<R: RangeBounds<usize>>(&self, range: R) -> HeaderVec<H, T>
+    where
+        H: Clone,
+        T: Clone,
+    {
+        let slice = &self.as_slice()[slice_range(range, self.len_strict())];
+        let mut new_vec = self.with_capacity_like(slice.len(), self.clone_header());
+        for e in slice {
+            new_vec.push(e.clone());
+        }
+        new_vec
+    }
+
+    /// Returns the elements as a slice that is guaranteed to have at least one element, or `None`
+    /// if the vector is empty.
+    #[inline(always)]
+    pub fn as_nonempty_slice(&self) -> Option<&[T]> {
+        let slice = self.as_slice();
+        if slice.is_empty() {
+            None
+        } else {
+            Some(slice)
+        }
+    }
+
+    /// Turns the vector into a [`FrozenHeaderVec`], which can no longer be modified and can be
+    /// shared across threads by reference once all the elements have been appended.
+    #[inline(always)]
+    pub fn freeze(self) -> FrozenHeaderVec<H, T> {
+        FrozenHeaderVec { header_vec: self }
+    }
+
+    /// Asserts that the relaxed, acquire and exclusive reads of the length all agree, which must
+    /// hold whenever no other thread is appending. This is meant for tests, including those of
+    /// downstream crates, to check that they use the atomic API correctly.
+    #[cfg(feature = "testing")]
+    pub fn assert_len_consistent(&mut self) {
+        let relaxed = self.len();
+        let acquire = self.len_strict();
+        let exact = self.len_exact();
+        assert_eq!(relaxed, acquire, "relaxed and acquire lengths differ");
+        assert_eq!(acquire, exact, "acquire and exclusive lengths differ");
+    }
+
+    /// Extends the vector with an iterator of known length, reserving exactly enough room for
+    /// its elements up front.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn extend_exact<I>(&mut self, iter: I) -> Option<*const ()>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let old_ptr = self.ptr();
+        let iter = iter.into_iter();
+        self.reserve_exact(iter.len());
+        for item in iter {
+            self.push(item);
+        }
+        if self.is(old_ptr) {
+            None
+        } else {
+            Some(old_ptr)
+        }
+    }
+
+    /// Appends clones of the elements an iterator of references yields, reserving room for the
+    /// lower bound of its size hint up front.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn extend_cloned<'a, I>(&mut self, iter: I) -> Option<*const ()>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: Clone + 'a,
+    {
+        let old_ptr = self.ptr();
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.push(item.clone());
+        }
+        if self.is(old_ptr) {
+            None
+        } else {
+            Some(old_ptr)
+        }
+    }
+
+    /// Returns mutable references to the header and to the uninitialized spare capacity at the same
+    /// time. After writing elements into the spare capacity, use [`HeaderVec::set_len`] to make
+    /// them part of the vector.
+    #[inline(always)]
+    pub fn header_and_spare_mut(&mut self) -> (&mut H, &mut [MaybeUninit<T>]) {
+        let len = self.len_exact();
+        let spare = self.capacity() - len;
+        let spare_ptr = unsafe { self.start_ptr_mut().add(len) } as *mut MaybeUninit<T>;
+        unsafe {
+            (
+                &mut (*(self.ptr as *mut HeaderVecHeader<H>)).head,
+                core::slice::from_raw_parts_mut(spare_ptr, spare),
+            )
+        }
+    }
+
+    /// Sets the length of the vector.
+    ///
+    /// # Safety
+    ///
+    /// `new_len` must be less than or equal to the capacity, and the elements up to `new_len` must
+    /// be initialized. Elements beyond `new_len` are not dropped.
+    #[inline(always)]
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.capacity());
+        self.set_len_exact(new_len);
+    }
+
+    /// Same as [`Clone::clone`], but since the elements are `Copy`, they are all copied at once
+    /// instead of being cloned one at a time.
+    pub fn clone_copy(&self) -> Self
+    where
+        H: Clone,
+        T: Copy,
+    {
+        let mut new_vec = self.with_capacity_like(self.len_strict(), self.clone_header());
+        new_vec.extend_from_slice_copy(self.as_slice());
+        new_vec
+    }
+
+    /// Appends clones of the elements to `out`.
+    #[inline(always)]
+    pub fn clone_into_vec(&self, out: &mut Vec<T>)
+    where
+        T: Clone,
+    {
+        out.extend_from_slice(self.as_slice());
+    }
+
+    /// Shrinks the capacity to the next power of two of the length if less than a quarter of the
+    /// capacity is in use, and does nothing otherwise. This can be called periodically on idle
+    /// vectors to reclaim memory.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn auto_shrink(&mut self) -> Option<*const ()> {
+        if !Self::element_size_nonzero() {
+            return None;
+        }
+        let len = self.len_exact();
+        if len * 4 < self.capacity() {
+            self.resize_cold(len.next_power_of_two())
+        } else {
+            None
+        }
+    }
+
+    /// Gives the smallest offset in units of T (as if the pointer started at an array of T) that the slice can start at.
+    #[inline(always)]
+    fn min_offset() -> usize {
+        if !Self::element_size_nonzero() {
+            // Zero-sized elements don't occupy any memory, so they don't need to be placed after the header.
+            return 0;
+        }
+        // The first location, in units of size_of::<T>(), that is after the header
+        // It's the end of the header, rounded up to the nearest size_of::<T>()
+        mem::size_of::<HeaderVecHeader<H>>().div_ceil(mem::size_of::<T>())
+    }
+
+    /// Gives the offset in units of T (as if the pointer started at an array of T) that the slice actually starts at.
+    #[inline(always)]
+    fn offset(&self) -> usize {
+        if self.capacity_word() & STORED_OFFSET_FLAG == 0 {
+            Self::min_offset()
+        } else {
+            unsafe { *self.stored_offset_ptr() }
+        }
+    }
+
+    /// Records where the elements start. Only an offset past [`HeaderVec::min_offset`] is written
+    /// to memory, see [`STORED_OFFSET_FLAG`].
+    fn set_offset(&mut self, offset: usize) {
+        if offset == Self::min_offset() {
+            self.header_mut().capacity &= !STORED_OFFSET_FLAG;
+        } else {
+            debug_assert!(
+                offset * mem::size_of::<T>()
+                    >= mem::size_of::<HeaderVecHeader<H>>() + mem::size_of::<usize>(),
+                "no room to store the offset"
+            );
+            unsafe { self.stored_offset_ptr().write(offset) };
+            self.header_mut().capacity |= STORED_OFFSET_FLAG;
+        }
+    }
+
+    /// Gets a pointer to the offset stored after the header when [`STORED_OFFSET_FLAG`] is set.
+    /// The header's size is a multiple of its alignment, which is at least that of `usize`, so the
+    /// pointer is aligned.
+    #[inline(always)]
+    fn stored_offset_ptr(&self) -> *mut usize {
+        unsafe { (self.ptr as *mut u8).add(mem::size_of::<HeaderVecHeader<H>>()) as *mut usize }
+    }
+
+    /// Whether the vector was created with [`HeaderVec::with_capacity_exact_growth`].
+    #[inline(always)]
+    fn exact_growth(&self) -> bool {
+        self.capacity_word() & EXACT_GROWTH_FLAG != 0
+    }
+
+    /// Reads the capacity word, flags included, without creating a reference to the header.
+    #[inline(always)]
+    fn capacity_word(&self) -> usize {
+        unsafe { *ptr::addr_of!((*(self.ptr as *const HeaderVecHeader<H>)).capacity) }
+    }
+
+    /// Compute the number of elements (in units of T) to allocate for a given capacity.
+    #[inline(always)]
+    fn elems_to_mem_elems(offset: usize, capacity: usize) -> usize {
+        offset + capacity
+    }
+
+    /// Compute the number of elements (in units of T) to allocate for a given capacity.
+    #[inline(always)]
+    fn elems_to_mem_bytes(offset: usize, capacity: usize) -> usize {
+        // With zero-sized elements, only the header needs to be allocated.
+        cmp::max(
+            Self::elems_to_mem_elems(offset, capacity) * mem::size_of::<T>(),
+            mem::size_of::<HeaderVecHeader<H>>(),
+        )
+    }
+
+    /// Compute the number of elements (in units of T) to allocate for a given capacity.
+    #[inline(always)]
+    fn layout(offset: usize, capacity: usize) -> alloc::alloc::Layout {
+        Self::try_layout(offset, capacity)
+            .expect("unable to produce memory layout for HeaderVec (is the capacity too large?)")
+    }
+
+    /// Same as [`HeaderVec::layout`], but returns an error instead of panicking or overflowing when
+    /// the allocation would be too large.
+    #[inline]
+    fn try_layout(offset: usize, capacity: usize) -> Result<alloc::alloc::Layout, TryReserveError> {
+        // The highest bits of the capacity word hold flags.
+        if capacity > CAPACITY_MASK && Self::element_size_nonzero() {
+            return Err(TryReserveError::CapacityOverflow);
+        }
+        let bytes = offset
+            .checked_add(capacity)
+            .and_then(|elems| elems.checked_mul(mem::size_of::<T>()))
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        alloc::alloc::Layout::from_size_align(
+            // With zero-sized elements, only the header needs to be allocated.
+            cmp::max(bytes, mem::size_of::<HeaderVecHeader<H>>()),
+            // The header also holds the length and capacity, so it can be more aligned than `H`.
+            cmp::max(mem::align_of::<HeaderVecHeader<H>>(), mem::align_of::<T>()),
+        )
+        .map_err(|_| TryReserveError::CapacityOverflow)
+    }
+
+    /// Gets the pointer to the start of the slice.
+    #[inline(always)]
+    fn start_ptr(&self) -> *const T {
+        if !Self::element_size_nonzero() {
+            // Zero-sized elements have no storage, but slices still need an aligned pointer.
+            return NonNull::dangling().as_ptr();
+        }
+        unsafe { self.ptr.add(self.offset()) }
+    }
+
+    /// Gets the pointer to the start of the slice.
+    #[inline(always)]
+    fn start_ptr_mut(&mut self) -> *mut T {
+        if !Self::element_size_nonzero() {
+            return NonNull::dangling().as_ptr();
+        }
+        unsafe { self.ptr.add(self.offset()) }
+    }
+
+    /// Gets a pointer to just the length in the header. Reading the length through this pointer
+    /// never creates a reference to the rest of the header, so it can't conflict with element
+    /// slices that are alive at the same time.
+    #[cfg(feature = "atomic_append")]
+    #[inline(always)]
+    fn len_ptr(&self) -> *const AtomicUsize {
+        unsafe { ptr::addr_of!((*(self.ptr as *const HeaderVecHeader<H>)).len) }
+    }
+
+    /// Gets a pointer to just the length in the header. Reading the length through this pointer
+    /// never creates a reference to the rest of the header, so it can't conflict with element
+    /// slices that are alive at the same time.
+    #[cfg(not(feature = "atomic_append"))]
+    #[inline(always)]
+    fn len_ptr(&self) -> *const usize {
+        unsafe { ptr::addr_of!((*(self.ptr as *const HeaderVecHeader<H>)).len) }
+    }
+
+    /// References to the header are always derived from the raw `self.ptr` and only span the
+    /// header itself, so they can coexist with pointers into the element region.
+    #[inline(always)]
+    fn header(&self) -> &HeaderVecHeader<H> {
+        // The beginning of the memory is always the header.
+        unsafe { &*(self.ptr as *const HeaderVecHeader<H>) }
+    }
+
+    #[inline(always)]
+    fn header_mut(&mut self) -> &mut HeaderVecHeader<H> {
+        // The beginning of the memory is always the header.
+        unsafe { &mut *(self.ptr as *mut HeaderVecHeader<H>) }
+    }
+}
+
+/// The atomic append API is only enabled when the `atomic_append` feature flag is set. The [`try_push_atomic`](HeaderVec::try_push_atomic) and the other `_atomic` methods
+/// allow a single producer to append through a shared reference while other threads read the
+/// elements that have been published so far. Appending atomically never reallocates, so the
+/// producer has to reserve enough capacity beforehand through exclusive access.
+#[cfg(feature = "atomic_append")]
+impl<H, T> HeaderVec<H, T> {
+    /// Get the length of the vector with relaxed ordering.
+    #[inline(always)]
+    pub fn len_atomic_relaxed(&self) -> usize {
+        self.header().len.load(Ordering::Relaxed)
+    }
+
+    /// Reads the length once through exclusive access, so it can be kept in a local variable
+    /// instead of doing a relaxed load with [`HeaderVec::len`] on every iteration of a loop. Since
+    /// no other thread can append while the vector is borrowed mutably, the cached value stays
+    /// correct for as long as the borrow lasts.
+    #[must_use]
+    #[inline(always)]
+    pub fn len_cached(&mut self) -> usize {
+        self.len_exact()
+    }
+
+    /// Get the length of the vector with acquire ordering.
+    #[inline(always)]
+    pub fn len_atomic_acquire(&self) -> usize {
+        self.header().len.load(Ordering::Acquire)
+    }
+
+    /// Add `n` to the length of the vector with release ordering and return the previous length.
+    ///
+    /// # Safety
+    ///
+    /// The `n` elements after the current length must be initialized and fit in the capacity.
+    #[inline(always)]
+    pub unsafe fn len_atomic_add_release(&self, n: usize) -> usize {
+        self.header().len.fetch_add(n, Ordering::Release)
     }
 
-    /// Gives the offset in units of T (as if the pointer started at an array of T) that the slice actually starts at.
+    /// Set the length of the vector with release ordering, publishing the elements up to `new_len`
+    /// to the threads that read the length with acquire ordering.
+    ///
+    /// # Safety
+    ///
+    /// `new_len` must be less than or equal to the capacity, and the elements up to `new_len` must
+    /// be initialized. Only one thread may append to the vector at any time.
     #[inline(always)]
-    fn offset() -> usize {
-        // The first location, in units of size_of::<T>(), that is after the header
-        // It's the end of the header, rounded up to the nearest size_of::<T>()
-        (mem::size_of::<HeaderVecHeader<H>>() + mem::size_of::<T>() - 1) / mem::size_of::<T>()
+    pub unsafe fn set_len_atomic(&self, new_len: usize) {
+        debug_assert!(new_len <= self.capacity());
+        self.header().len.store(new_len, Ordering::Release);
     }
 
-    /// Compute the number of elements (in units of T) to allocate for a given capacity.
-    #[inline(always)]
-    fn elems_to_mem_elems(capacity: usize) -> usize {
-        Self::offset() + capacity
+    /// Atomically adds an item to the end of the list without reallocating.
+    ///
+    /// Returns `Err(item)` when the vector is full. Appending atomically can never grow the
+    /// vector, so to make room, stop all appends, then use exclusive access to grow it (for
+    /// instance with [`HeaderVec::reserve`]) and push the returned item again. Remember to update
+    /// the weak references if the memory moved.
+    ///
+    /// # Safety
+    ///
+    /// Only one thread may append to the vector at any time.
+    pub unsafe fn try_push_atomic(&self, item: T) -> Result<(), T> {
+        // Relaxed is enough here since this is the only thread that stores to the length.
+        let len = self.len_atomic_relaxed();
+        if len < self.capacity() {
+            ptr::write(self.ptr.add(self.offset() + len), item);
+            let len_again = self.len_atomic_add_release(1);
+            debug_assert_eq!(len_again, len, "len was updated by another thread");
+            self.bump_version_atomic();
+            Ok(())
+        } else {
+            Err(item)
+        }
     }
 
-    /// Compute the number of elements (in units of T) to allocate for a given capacity.
+    /// Use [`HeaderVec::try_push_atomic`] instead.
+    ///
+    /// # Safety
+    ///
+    /// Only one thread may append to the vector at any time.
+    #[deprecated(note = "use `try_push_atomic`, which makes it clear that appending can fail")]
     #[inline(always)]
-    fn elems_to_mem_bytes(capacity: usize) -> usize {
-        Self::elems_to_mem_elems(capacity) * mem::size_of::<T>()
+    pub unsafe fn push_atomic(&self, item: T) -> Result<(), T> {
+        self.try_push_atomic(item)
     }
 
-    /// Compute the number of elements (in units of T) to allocate for a given capacity.
-    #[inline(always)]
-    fn layout(capacity: usize) -> alloc::alloc::Layout {
-        alloc::alloc::Layout::from_size_align(
-            Self::elems_to_mem_bytes(capacity),
-            cmp::max(mem::align_of::<H>(), mem::align_of::<T>()),
-        )
-        .expect("unable to produce memory layout with Hrc key type (is it a zero sized type? they are not permitted)")
+    /// Atomically appends a copy of `slice` without reallocating, and returns the index at which
+    /// it starts.
+    ///
+    /// If the whole slice doesn't fit, nothing is appended and `Err` holds the number of elements
+    /// that would have fit.
+    ///
+    /// # Safety
+    ///
+    /// Only one thread may append to the vector at any time.
+    pub unsafe fn push_atomic_slice_at(&self, slice: &[T]) -> Result<usize, usize>
+    where
+        T: Copy,
+    {
+        let len = self.len_atomic_relaxed();
+        let spare = self.capacity() - len;
+        if slice.len() > spare {
+            return Err(spare);
+        }
+        ptr::copy_nonoverlapping(
+            slice.as_ptr(),
+            (self.start_ptr() as *mut T).add(len),
+            slice.len(),
+        );
+        let len_again = self.len_atomic_add_release(slice.len());
+        debug_assert_eq!(len_again, len, "len was updated by another thread");
+        self.bump_version_atomic();
+        Ok(len)
     }
 
-    /// Gets the pointer to the start of the slice.
+    /// Returns how many successful atomic appends were made to the vector so far, through
+    /// [`HeaderVec::try_push_atomic`] or [`HeaderVec::push_atomic_slice_at`].
+    ///
+    /// The counter only ever increases, so a reader can compare two versions to find out whether
+    /// new elements arrived in between. It is meant for the logic of the reader, the length is
+    /// what makes reading the elements safe.
     #[inline(always)]
-    fn start_ptr(&self) -> *const T {
-        unsafe { self.ptr.add(Self::offset()) }
+    pub fn version_atomic(&self) -> usize {
+        self.header().version.load(Ordering::Acquire)
     }
 
-    /// Gets the pointer to the start of the slice.
     #[inline(always)]
-    fn start_ptr_mut(&mut self) -> *mut T {
-        unsafe { self.ptr.add(Self::offset()) }
+    fn bump_version_atomic(&self) {
+        self.header().version.fetch_add(1, Ordering::Release);
     }
 
-    #[inline(always)]
-    fn header(&self) -> &HeaderVecHeader<H> {
-        // The beginning of the memory is always the header.
-        unsafe { &*(self.ptr as *const HeaderVecHeader<H>) }
+    /// Returns up to `max` elements that were published since `*cursor` and advances the cursor
+    /// past them. A consumer can call this repeatedly to process the elements appended by
+    /// [`HeaderVec::try_push_atomic`] in batches. An empty slice means no new elements are available yet.
+    pub fn consume_batch_atomic(&self, cursor: &mut usize, max: usize) -> &[T] {
+        let len = self.len_atomic_acquire();
+        let start = cmp::min(*cursor, len);
+        let end = cmp::min(start.saturating_add(max), len);
+        *cursor = end;
+        unsafe { core::slice::from_raw_parts(self.start_ptr().add(start), end - start) }
     }
+}
 
-    #[inline(always)]
-    fn header_mut(&mut self) -> &mut HeaderVecHeader<H> {
-        // The beginning of the memory is always the header.
-        unsafe { &mut *(self.ptr as *mut HeaderVecHeader<H>) }
+/// The fallible allocation API is only enabled when the `fallible_alloc` feature flag is set. The
+/// [`try_with_capacity`](HeaderVec::try_with_capacity), [`try_reserve`](HeaderVec::try_reserve),
+/// [`try_push`](HeaderVec::try_push) and [`try_extend`](HeaderVec::try_extend) methods return a
+/// [`TryReserveError`] instead of aborting through
+/// [`handle_alloc_error`](alloc::alloc::handle_alloc_error) when memory can't be allocated.
+///
+/// Code that must never abort has to stick to these methods and to the ones that never allocate.
+/// Every other method that allocates or reallocates still aborts on failure, in particular
+/// [`new`](HeaderVec::new), [`with_capacity`](HeaderVec::with_capacity) and the other constructors,
+/// [`push`](HeaderVec::push), [`extend_from_slice`](HeaderVec::extend_from_slice) and the other
+/// appends, [`reserve`](HeaderVec::reserve), [`shrink_to_fit`](HeaderVec::shrink_to_fit),
+/// [`set_capacity`](HeaderVec::set_capacity) and [`Clone::clone`].
+#[cfg(feature = "fallible_alloc")]
+impl<H, T> HeaderVec<H, T> {
+    /// Same as [`HeaderVec::with_capacity`], but returns an error if the memory can't be allocated.
+    /// The header is dropped in that case.
+    pub fn try_with_capacity(capacity: usize, head: H) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_and_offset(capacity, Self::min_offset(), head, false)
+    }
+
+    /// Same as [`HeaderVec::reserve`], but returns an error if the memory can't be allocated. The
+    /// vector is left untouched in that case.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<Option<*const ()>, TryReserveError> {
+        let required = self
+            .len_exact()
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        let old_capacity = self.capacity();
+        if required <= old_capacity {
+            return Ok(None);
+        }
+        let new_capacity = Self::next_capacity(old_capacity, required, self.exact_growth());
+        let offset = self.offset();
+        self.try_realloc_cold(offset, offset, new_capacity)
+    }
+
+    /// Same as [`HeaderVec::push`], but returns an error if the vector is full and can't grow. The
+    /// item is handed back along with the error in that case.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn try_push(&mut self, item: T) -> Result<Option<*const ()>, (T, TryReserveError)> {
+        let previous_pointer = match self.try_reserve(1) {
+            Ok(previous_pointer) => previous_pointer,
+            Err(error) => return Err((item, error)),
+        };
+        let old_len = self.len_exact();
+        unsafe {
+            ptr::write(self.start_ptr_mut().add(old_len), item);
+        }
+        self.set_len_exact(old_len + 1);
+        Ok(previous_pointer)
+    }
+
+    /// Same as [`Extend::extend`], but stops at the first item that doesn't fit and returns it
+    /// along with the error. The items pushed before the error stay in the vector, and the rest are
+    /// left in the iterator, so pass `&mut iter` to keep them.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location, even when an error
+    /// is returned. In this case, you are responsible for updating the weak nodes.
+    pub fn try_extend<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<Option<*const ()>, (Option<*const ()>, T, TryReserveError)> {
+        let old_ptr = self.ptr();
+        let moved = |this: &Self| {
+            if this.is(old_ptr) {
+                None
+            } else {
+                Some(old_ptr)
+            }
+        };
+        let iter = iter.into_iter();
+        // The size hint is only a hint, so failing to reserve it up front isn't an error yet.
+        let _ = self.try_reserve(iter.size_hint().0);
+        for item in iter {
+            if let Err((item, error)) = self.try_push(item) {
+                return Err((moved(self), item, error));
+            }
+        }
+        Ok(moved(self))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<H, T: Send> HeaderVec<H, T> {
+    /// Extends the vector with the items of a parallel iterator.
+    ///
+    /// The items are collected in parallel, then appended in bulk after a single reserve, since
+    /// appending to the vector itself can't be done concurrently.
+    ///
+    /// Returns the previous pointer if the memory was moved to a new location.
+    /// In this case, you are responsible for updating the weak nodes.
+    pub fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, iter: I) -> Option<*const ()> {
+        let mut items: Vec<T> = iter.into_par_iter().collect();
+        let previous_pointer = self.reserve(items.len());
+        let old_len = self.len_exact();
+        unsafe {
+            ptr::copy_nonoverlapping(
+                items.as_ptr(),
+                self.start_ptr_mut().add(old_len),
+                items.len(),
+            );
+            self.set_len_exact(old_len + items.len());
+            // The items were moved into the vector, so they must not be dropped by `items`.
+            items.set_len(0);
+        }
+        previous_pointer
     }
 }
 
@@ -301,10 +2264,13 @@ impl<H, T> Drop for HeaderVec<H, T> {
     fn drop(&mut self) {
         unsafe {
             ptr::drop_in_place(&mut self.header_mut().head);
-            for ix in 0..self.len() {
+            for ix in 0..self.len_exact() {
                 ptr::drop_in_place(self.start_ptr_mut().add(ix));
             }
-            alloc::alloc::dealloc(self.ptr as *mut u8, Self::layout(self.capacity()));
+            alloc::alloc::dealloc(
+                self.ptr as *mut u8,
+                Self::layout(self.offset(), self.capacity()),
+            );
         }
     }
 }
@@ -353,7 +2319,34 @@ where
     T: PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.header().head == other.header().head && self.as_slice() == other.as_slice()
+        self.header_eq(other) && self.elements_eq(other)
+    }
+}
+
+impl<H, T> PartialEq<[T]> for HeaderVec<H, T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &[T]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<H, T> PartialEq<&[T]> for HeaderVec<H, T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &&[T]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl<H, T> PartialEq<Vec<T>> for HeaderVec<H, T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self.as_slice() == other.as_slice()
     }
 }
 
@@ -363,7 +2356,7 @@ where
     T: Clone,
 {
     fn clone(&self) -> Self {
-        let mut new_vec = Self::with_capacity(self.len(), self.header().head.clone());
+        let mut new_vec = self.with_capacity_like(self.len(), self.header().head.clone());
         for e in self.as_slice() {
             new_vec.push(e.clone());
         }
@@ -384,8 +2377,414 @@ where
     }
 }
 
+/// An iterator which removes the elements matching a predicate, created by [`HeaderVec::extract_if`].
+pub struct ExtractIf<'a, H, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    vec: &'a mut HeaderVec<H, T>,
+    /// The index of the next element to visit.
+    index: usize,
+    /// The number of elements removed so far.
+    deleted: usize,
+    old_len: usize,
+    pred: F,
+}
+
+impl<H, T, F> Iterator for ExtractIf<'_, H, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let start_ptr = self.vec.start_ptr_mut();
+        while self.index < self.old_len {
+            unsafe {
+                let current = start_ptr.add(self.index);
+                let extract = (self.pred)(&mut *current);
+                self.index += 1;
+                if extract {
+                    self.deleted += 1;
+                    return Some(ptr::read(current));
+                } else if self.deleted > 0 {
+                    // Move the retained element back to close the gap left by the removed ones.
+                    ptr::copy_nonoverlapping(current, current.sub(self.deleted), 1);
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.old_len - self.index))
+    }
+}
+
+impl<H, T, F> Drop for ExtractIf<'_, H, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    fn drop(&mut self) {
+        // Move the elements that were never visited back to close the gap.
+        if self.deleted > 0 && self.index < self.old_len {
+            let start_ptr = self.vec.start_ptr_mut();
+            unsafe {
+                ptr::copy(
+                    start_ptr.add(self.index),
+                    start_ptr.add(self.index - self.deleted),
+                    self.old_len - self.index,
+                );
+            }
+        }
+        self.vec.set_len_exact(self.old_len - self.deleted);
+    }
+}
+
+/// A [`HeaderVec`] whose header is not initialized yet, created by [`HeaderVec::with_capacity_uninit_header`].
+pub struct UninitHeaderVec<H, T> {
+    header_vec: HeaderVec<MaybeUninit<H>, T>,
+}
+
+impl<H, T> UninitHeaderVec<H, T> {
+    /// Gives access to the uninitialized header.
+    #[inline(always)]
+    pub fn header_mut(&mut self) -> &mut MaybeUninit<H> {
+        &mut self.header_vec
+    }
+
+    /// Converts this into a regular `HeaderVec`.
+    ///
+    /// # Safety
+    ///
+    /// The header must have been initialized through [`UninitHeaderVec::header_mut`].
+    #[inline(always)]
+    pub unsafe fn assume_header_init(self) -> HeaderVec<H, T> {
+        let header_vec = ManuallyDrop::new(self.header_vec);
+        HeaderVec {
+            ptr: header_vec.ptr,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Adds up capacity hints before allocating a [`HeaderVec`] once, created by [`HeaderVec::builder`].
+///
+/// ```
+/// use header_vec::HeaderVec;
+///
+/// let hv = HeaderVec::<(), u32>::builder(()).reserve(10).reserve(20).build();
+/// assert_eq!(hv.capacity(), 30);
+/// ```
+#[derive(Debug)]
+pub struct HeaderVecBuilder<H, T> {
+    head: H,
+    capacity: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<H, T> HeaderVecBuilder<H, T> {
+    /// Adds room for `additional` elements to the capacity that will be allocated.
+    #[inline(always)]
+    pub fn reserve(mut self, additional: usize) -> Self {
+        self.capacity = self
+            .capacity
+            .checked_add(additional)
+            .expect("HeaderVec capacity overflow");
+        self
+    }
+
+    /// Allocates the `HeaderVec` with the total capacity of all the hints.
+    #[inline(always)]
+    pub fn build(self) -> HeaderVec<H, T> {
+        HeaderVec::with_capacity(self.capacity, self.head)
+    }
+}
+
+/// An iterator which removes a range of elements, created by [`HeaderVec::drain`].
+pub struct Drain<'a, H, T> {
+    vec: &'a mut HeaderVec<H, T>,
+    /// The index of the next element to yield from the front.
+    index: usize,
+    /// One past the index of the next element to yield from the back.
+    end: usize,
+    tail_start: usize,
+    tail_len: usize,
+    /// Whether to shrink the vector to fit once the gap is closed.
+    shrink: bool,
+}
+
+impl<H, T> Iterator for Drain<'_, H, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.index == self.end {
+            return None;
+        }
+        let item = unsafe { ptr::read(self.vec.start_ptr().add(self.index)) };
+        self.index += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<H, T> DoubleEndedIterator for Drain<'_, H, T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.index == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(unsafe { ptr::read(self.vec.start_ptr().add(self.end)) })
+    }
+}
+
+impl<H, T> ExactSizeIterator for Drain<'_, H, T> {}
+
+impl<H, T> Drop for Drain<'_, H, T> {
+    fn drop(&mut self) {
+        let start_ptr = self.vec.start_ptr_mut();
+        let start = self.vec.len_exact();
+        unsafe {
+            // Drop the elements that were not consumed.
+            let remaining =
+                ptr::slice_from_raw_parts_mut(start_ptr.add(self.index), self.end - self.index);
+            self.index = self.end;
+            ptr::drop_in_place(remaining);
+            // Move the tail back to close the gap.
+            if self.tail_start != start {
+                ptr::copy(
+                    start_ptr.add(self.tail_start),
+                    start_ptr.add(start),
+                    self.tail_len,
+                );
+            }
+        }
+        self.vec.set_len_exact(start + self.tail_len);
+        if self.shrink {
+            self.vec.shrink_to_fit();
+        }
+    }
+}
+
+/// Resolves a range of indices against a slice of length `len`.
+///
+/// # Panics
+///
+/// Panics if the range is out of bounds or its start is greater than its end.
+fn slice_range(range: impl RangeBounds<usize>, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start index overflowed"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end index overflowed"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end,
+        "range start index {start} is greater than end index {end}"
+    );
+    assert!(
+        end <= len,
+        "range end index {end} out of range for HeaderVec of length {len}"
+    );
+    start..end
+}
+
+/// Writing appends the bytes to the vector. The memory may be moved to a new location while
+/// writing, so check [`HeaderVec::is`] afterwards if you need to update weak references.
+#[cfg(feature = "std")]
+impl<H> std::io::Write for HeaderVec<H, u8> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice_copy(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.extend_from_slice_copy(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writing appends the UTF-8 bytes of the string to the vector. The memory may be moved to a new
+/// location while writing, so check [`HeaderVec::is`] afterwards if you need to update weak references.
+impl<H> core::fmt::Write for HeaderVec<H, u8> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.extend_from_slice_copy(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// A [`HeaderVec`] that can no longer be modified, created by [`HeaderVec::freeze`].
+///
+/// It dereferences to the slice of elements, and the header is available through
+/// [`FrozenHeaderVec::header`].
+pub struct FrozenHeaderVec<H, T> {
+    header_vec: HeaderVec<H, T>,
+}
+
+impl<H, T> FrozenHeaderVec<H, T> {
+    /// Gets a reference to the header.
+    #[inline(always)]
+    pub fn header(&self) -> &H {
+        &self.header_vec
+    }
+}
+
+impl<H, T> Deref for FrozenHeaderVec<H, T> {
+    type Target = [T];
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.header_vec.as_slice()
+    }
+}
+
+impl<H, T> Debug for FrozenHeaderVec<H, T>
+where
+    H: Debug,
+    T: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FrozenHeaderVec")
+            .field("header", self.header())
+            .field("vec", &&**self)
+            .finish()
+    }
+}
+
+impl<H, T> IntoIterator for HeaderVec<H, T> {
+    type Item = T;
+    type IntoIter = IntoIter<H, T>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        let end = self.len_exact();
+        // The iterator owns the elements now, so the vector must only drop its header and memory.
+        self.set_len_exact(0);
+        IntoIter {
+            header_vec: self,
+            start: 0,
+            end,
+        }
+    }
+}
+
+/// An iterator that moves the elements out of a [`HeaderVec`], created by [`IntoIterator::into_iter`].
+pub struct IntoIter<H, T> {
+    header_vec: HeaderVec<H, T>,
+    /// The index of the next element to yield from the front.
+    start: usize,
+    /// One past the index of the next element to yield from the back.
+    end: usize,
+}
+
+impl<H, T> IntoIter<H, T> {
+    /// Gets a reference to the header of the vector being iterated.
+    #[inline(always)]
+    pub fn header(&self) -> &H {
+        &self.header_vec
+    }
+
+    /// Returns the elements that have not been yielded yet.
+    #[must_use]
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe {
+            core::slice::from_raw_parts(
+                self.header_vec.start_ptr().add(self.start),
+                self.end - self.start,
+            )
+        }
+    }
+}
+
+impl<H, T> Iterator for IntoIter<H, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        let item = unsafe { ptr::read(self.header_vec.start_ptr().add(self.start)) };
+        self.start += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.start;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<H, T> DoubleEndedIterator for IntoIter<H, T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(unsafe { ptr::read(self.header_vec.start_ptr().add(self.end)) })
+    }
+}
+
+impl<H, T> ExactSizeIterator for IntoIter<H, T> {}
+
+impl<H, T> Drop for IntoIter<H, T> {
+    fn drop(&mut self) {
+        // Only the elements that were not consumed from either end are still owned by the iterator.
+        let remaining = ptr::slice_from_raw_parts_mut(
+            unsafe { self.header_vec.start_ptr_mut().add(self.start) },
+            self.end - self.start,
+        );
+        self.start = self.end;
+        unsafe { ptr::drop_in_place(remaining) };
+    }
+}
+
 pub struct HeaderVecWeak<H, T> {
     header_vec: ManuallyDrop<HeaderVec<H, T>>,
+    /// A weak reference aliases a `HeaderVec` that may be mutated or freed by its owner at any time,
+    /// so it must stay on the owner's thread, as it did before `HeaderVec` was `Send` and `Sync`.
+    _not_send_sync: PhantomData<*const ()>,
+}
+
+impl<H, T> HeaderVecWeak<H, T> {
+    /// Same as [`HeaderVec::len`] on the vector this weak points to.
+    #[must_use]
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.header_vec.len()
+    }
+
+    /// Same as [`HeaderVec::is_empty`] on the vector this weak points to.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.header_vec.is_empty()
+    }
+
+    /// Same as [`HeaderVec::as_slice`] on the vector this weak points to.
+    #[must_use]
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[T] {
+        self.header_vec.as_slice()
+    }
+
+    /// Returns a reference to the element at `index` of the vector this weak points to, or `None`
+    /// if it is out of bounds.
+    #[inline(always)]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
 }
 
 impl<H, T> Deref for HeaderVecWeak<H, T> {
@@ -0,0 +1,42 @@
+extern crate std;
+
+use header_vec::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+
+/// Returns memory that has exactly the requested alignment and no more, so that any allocation
+/// that asks for too small an alignment ends up misaligned.
+struct ExactAlignAllocator;
+
+fn padded_layout(layout: Layout) -> Layout {
+    Layout::from_size_align(layout.size() + layout.align(), layout.align() * 2).unwrap()
+}
+
+unsafe impl GlobalAlloc for ExactAlignAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(padded_layout(layout));
+        if ptr.is_null() {
+            return ptr;
+        }
+        // `ptr` is aligned to twice the alignment, so this is aligned to exactly the alignment.
+        ptr.add(layout.align())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr.sub(layout.align()), padded_layout(layout))
+    }
+}
+
+#[global_allocator]
+static GLOBAL: ExactAlignAllocator = ExactAlignAllocator;
+
+#[test]
+fn test_header_aligned_for_small_types() {
+    let mut hv = HeaderVec::<u8, u8>::new(1);
+    for i in 0..100 {
+        hv.push(i);
+        // The length and capacity are stored next to the header, so it must be aligned for them.
+        assert_eq!(hv.ptr() as usize % core::mem::align_of::<usize>(), 0);
+    }
+    assert_eq!(*hv, 1);
+    assert_eq!(hv.len(), 100);
+}
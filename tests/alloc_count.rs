@@ -0,0 +1,73 @@
+extern crate std;
+
+use header_vec::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts the allocations made by each thread, so that tests running in parallel don't see each
+/// other's allocations.
+struct CountingAllocator;
+
+std::thread_local! {
+    /// The allocations made by this thread that are currently alive.
+    static LIVE_ALLOCATIONS: Cell<isize> = const { Cell::new(0) };
+    /// Every allocation and reallocation made by this thread.
+    static TOTAL_ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = LIVE_ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        let _ = TOTAL_ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = LIVE_ALLOCATIONS.try_with(|count| count.set(count.get() - 1));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn live_allocations() -> isize {
+    LIVE_ALLOCATIONS.with(Cell::get)
+}
+
+fn total_allocations() -> usize {
+    TOTAL_ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn test_from_boxed_slice_with_header() {
+    let before = live_allocations();
+    let boxed: Box<[u64]> = (0..100).collect();
+    assert_eq!(live_allocations(), before + 1);
+
+    let hv = HeaderVec::from_boxed_slice_with_header('h', boxed);
+    // The box was freed, and only the `HeaderVec` is left.
+    assert_eq!(live_allocations(), before + 1);
+    assert_eq!(*hv, 'h');
+    assert_eq!(hv, (0..100).collect::<Vec<u64>>());
+
+    drop(hv);
+    assert_eq!(live_allocations(), before);
+}
+
+#[test]
+fn test_builder_allocates_once() {
+    let before = total_allocations();
+    let mut hv = HeaderVec::<(), u32>::builder(())
+        .reserve(10)
+        .reserve(0)
+        .reserve(22)
+        .build();
+    assert_eq!(total_allocations(), before + 1);
+    assert_eq!(hv.capacity(), 32);
+
+    for i in 0..32 {
+        hv.push(i);
+    }
+    assert_eq!(total_allocations(), before + 1);
+}
@@ -0,0 +1,192 @@
+#![cfg(feature = "atomic_append")]
+extern crate std;
+
+use header_vec::*;
+
+#[test]
+fn test_consume_batch_atomic() {
+    const N: usize = 1000;
+    const BATCH: usize = 16;
+    let hv = HeaderVec::<(), usize>::with_capacity(N, ());
+
+    let consumed = std::thread::scope(|s| {
+        s.spawn(|| {
+            let mut i = 0;
+            while i < N {
+                // Append irregularly sized bursts.
+                for _ in 0..(i % 7 + 1) {
+                    if i < N {
+                        unsafe { hv.try_push_atomic(i).unwrap() };
+                        i += 1;
+                    }
+                }
+                std::thread::yield_now();
+            }
+        });
+
+        let mut cursor = 0;
+        let mut consumed = std::vec::Vec::new();
+        while consumed.len() < N {
+            let batch = hv.consume_batch_atomic(&mut cursor, BATCH);
+            assert!(batch.len() <= BATCH);
+            consumed.extend_from_slice(batch);
+        }
+        assert_eq!(cursor, N);
+        assert!(hv.consume_batch_atomic(&mut cursor, BATCH).is_empty());
+        consumed
+    });
+
+    assert_eq!(consumed, (0..N).collect::<std::vec::Vec<_>>());
+}
+
+#[test]
+fn test_shrink_to_fit_after_atomic_append() {
+    let mut hv = HeaderVec::<(), usize>::with_capacity(32, ());
+    for i in 0..10 {
+        unsafe { hv.try_push_atomic(i).unwrap() };
+    }
+
+    hv.shrink_to_fit();
+    assert_eq!(hv.capacity(), 10);
+    assert_eq!(hv.len_exact(), 10);
+    assert_eq!(hv.as_slice(), (0..10).collect::<std::vec::Vec<_>>());
+}
+
+#[test]
+fn test_push_atomic_slice_at() {
+    let hv = HeaderVec::<(), u8>::with_capacity(8, ());
+    let first = [1, 2, 3];
+    let second = [4, 5];
+    assert_eq!(unsafe { hv.push_atomic_slice_at(&first) }, Ok(0));
+    assert_eq!(unsafe { hv.push_atomic_slice_at(&second) }, Ok(first.len()));
+    assert_eq!(unsafe { hv.push_atomic_slice_at(&[0; 4]) }, Err(3));
+    assert_eq!(hv.as_slice(), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn test_try_push_atomic_full() {
+    let mut hv = HeaderVec::<(), usize>::with_capacity(4, ());
+    for i in 0..4 {
+        assert_eq!(unsafe { hv.try_push_atomic(i) }, Ok(()));
+    }
+    assert_eq!(unsafe { hv.try_push_atomic(4) }, Err(4));
+
+    // Grow through exclusive access, then retry.
+    hv.reserve(1);
+    assert_eq!(unsafe { hv.try_push_atomic(4) }, Ok(()));
+    assert_eq!(hv.as_slice(), [0, 1, 2, 3, 4]);
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn test_len_consistent() {
+    let mut hv = HeaderVec::<(), usize>::with_capacity(8, ());
+    hv.assert_len_consistent();
+    for i in 0..16 {
+        if i % 2 == 0 {
+            hv.push(i);
+        } else if let Err(item) = unsafe { hv.try_push_atomic(i) } {
+            hv.push(item);
+        }
+        hv.assert_len_consistent();
+    }
+    assert_eq!(hv.as_slice(), (0..16).collect::<std::vec::Vec<_>>());
+}
+
+#[test]
+fn test_set_len_atomic() {
+    let hv = HeaderVec::<(), u32>::with_capacity(8, ());
+    let elements = hv.as_ptr() as *mut u32;
+    for i in 0..5 {
+        unsafe { elements.add(i).write(i as u32 * 7) };
+    }
+    unsafe { hv.set_len_atomic(5) };
+    assert_eq!(hv.len_atomic_acquire(), 5);
+    assert_eq!(hv.as_slice(), [0, 7, 14, 21, 28]);
+}
+
+#[test]
+fn test_version_atomic() {
+    const N: usize = 1000;
+    let hv = HeaderVec::<(), usize>::with_capacity(N + 2, ());
+    assert_eq!(hv.version_atomic(), 0);
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            for i in 0..N {
+                unsafe { hv.try_push_atomic(i).unwrap() };
+            }
+        });
+
+        let mut last = 0;
+        while last < N {
+            let version = hv.version_atomic();
+            assert!(version >= last);
+            // The version is bumped after the length, so every counted append is visible.
+            assert!(hv.len_atomic_acquire() >= version);
+            last = version;
+        }
+    });
+    assert_eq!(hv.version_atomic(), N);
+
+    // A failed append doesn't count.
+    unsafe { hv.push_atomic_slice_at(&[1, 2]).unwrap() };
+    assert!(unsafe { hv.try_push_atomic(0) }.is_err());
+    assert_eq!(hv.version_atomic(), N + 1);
+}
+
+#[test]
+fn test_extend_from_slice_mixed_with_atomic_push() {
+    let mut hv = HeaderVec::<(), u32>::with_capacity(2, ());
+    unsafe { hv.try_push_atomic(0).unwrap() };
+    hv.extend_from_slice(&[1, 2, 3, 4]);
+    assert!(hv.capacity() >= 5);
+    assert_eq!(hv.len_atomic_acquire(), 5);
+
+    hv.reserve(1);
+    unsafe { hv.try_push_atomic(5).unwrap() };
+    hv.extend_from_slice(&[6, 7]);
+    assert_eq!(hv.len_atomic_acquire(), 8);
+    assert_eq!(hv.as_slice(), [0, 1, 2, 3, 4, 5, 6, 7]);
+}
+
+#[test]
+fn test_len_cached() {
+    let mut hv = HeaderVec::<(), usize>::with_capacity(8, ());
+    for i in 0..5 {
+        unsafe { hv.try_push_atomic(i).unwrap() };
+    }
+    let len = hv.len_cached();
+    assert_eq!(len, hv.len());
+    for i in 0..len {
+        hv.as_mut_slice()[i] *= 2;
+    }
+    assert_eq!(hv.as_slice(), [0, 2, 4, 6, 8]);
+}
+
+#[test]
+fn test_snapshot() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const N: usize = 1000;
+    let hv = HeaderVec::<AtomicUsize, usize>::with_capacity(N, AtomicUsize::new(0));
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            for i in 0..N {
+                // The header announces the element before its length is published.
+                hv.store(i + 1, Ordering::Release);
+                unsafe { hv.try_push_atomic(i).unwrap() };
+            }
+        });
+
+        loop {
+            let (announced, slice) = hv.snapshot();
+            assert!(announced.load(Ordering::Acquire) >= slice.len());
+            assert!(slice.iter().enumerate().all(|(i, &x)| i == x));
+            if slice.len() == N {
+                break;
+            }
+        }
+    });
+}
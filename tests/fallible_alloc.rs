@@ -0,0 +1,94 @@
+#![cfg(feature = "fallible_alloc")]
+extern crate std;
+
+use header_vec::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+
+/// Fails every allocation larger than `LIMIT`, to simulate running out of memory.
+struct LimitedAllocator;
+
+const LIMIT: usize = 1 << 20;
+
+unsafe impl GlobalAlloc for LimitedAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() > LIMIT {
+            return core::ptr::null_mut();
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: LimitedAllocator = LimitedAllocator;
+
+#[test]
+fn test_try_push_out_of_memory() {
+    let mut hv = unsafe { HeaderVec::<(), u8>::with_capacity_zeroed_len(LIMIT / 2 + 1, ()) };
+    let len = hv.len();
+    let (item, err) = hv.try_push(1).unwrap_err();
+    assert_eq!(item, 1);
+    assert!(matches!(err, TryReserveError::AllocError { .. }));
+    // The vector is untouched.
+    assert_eq!(hv.len(), len);
+    assert_eq!(hv.capacity(), len);
+
+    // Exact growth only needs one more element, which still fits.
+    let mut hv = HeaderVec::<(), u8>::with_capacity_exact_growth(LIMIT / 2 + 1, ());
+    hv.try_extend(core::iter::repeat_n(0, LIMIT / 2 + 2))
+        .unwrap();
+    assert_eq!(hv.len(), LIMIT / 2 + 2);
+}
+
+#[test]
+fn test_try_extend_out_of_memory() {
+    let mut hv = HeaderVec::<(), u64>::new(());
+    let mut iter = 0..LIMIT as u64;
+    let (_, item, err) = hv.try_extend(&mut iter).unwrap_err();
+    assert!(matches!(err, TryReserveError::AllocError { .. }));
+    // The item that didn't fit is handed back and the rest are left in the iterator.
+    assert_eq!(item, hv.len() as u64);
+    assert_eq!(iter.next(), Some(item + 1));
+    // The items that fit stay in the vector.
+    assert!(!hv.is_empty());
+    assert!(hv
+        .as_slice()
+        .iter()
+        .enumerate()
+        .all(|(i, &x)| i as u64 == x));
+    assert!(hv.allocation_size() <= LIMIT);
+}
+
+#[test]
+fn test_try_reserve_capacity_overflow() {
+    let mut hv = HeaderVec::<(), u64>::new(());
+    hv.push(1);
+    assert_eq!(
+        hv.try_reserve(usize::MAX),
+        Err(TryReserveError::CapacityOverflow)
+    );
+    assert_eq!(
+        hv.try_reserve(usize::MAX / 4),
+        Err(TryReserveError::CapacityOverflow)
+    );
+    assert_eq!(hv.try_reserve(10).map(|_| ()), Ok(()));
+    assert_eq!(hv.as_slice(), [1]);
+}
+
+#[test]
+fn test_try_with_capacity_out_of_memory() {
+    let err = HeaderVec::<(), u8>::try_with_capacity(LIMIT + 1, ()).unwrap_err();
+    assert!(matches!(err, TryReserveError::AllocError { .. }));
+    assert_eq!(
+        HeaderVec::<(), u64>::try_with_capacity(usize::MAX / 2, ()).unwrap_err(),
+        TryReserveError::CapacityOverflow
+    );
+
+    let mut hv = HeaderVec::<_, u8>::try_with_capacity(16, 'h').unwrap();
+    assert_eq!(hv.capacity(), 16);
+    hv.try_push(1).unwrap();
+    assert_eq!(*hv, 'h');
+}
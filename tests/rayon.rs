@@ -0,0 +1,27 @@
+#![cfg(feature = "rayon")]
+extern crate std;
+
+use header_vec::*;
+use rayon::prelude::*;
+use std::vec::Vec;
+
+#[test]
+fn test_par_extend() {
+    let mut hv = HeaderVec::<_, u64>::new("parallel");
+    hv.push(u64::MAX);
+    hv.par_extend((0..10_000u64).into_par_iter().map(|i| i * 3));
+
+    let mut baseline = vec![u64::MAX];
+    baseline.extend((0..10_000u64).map(|i| i * 3));
+
+    assert_eq!(*hv, "parallel");
+    assert_eq!(hv, baseline);
+}
+
+#[test]
+fn test_par_extend_owned_elements() {
+    let mut hv = HeaderVec::<(), Vec<usize>>::new(());
+    hv.par_extend((0..100usize).into_par_iter().map(|i| vec![i; i]));
+    assert_eq!(hv.len(), 100);
+    assert!(hv.as_slice().iter().enumerate().all(|(i, v)| v.len() == i));
+}
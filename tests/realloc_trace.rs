@@ -0,0 +1,53 @@
+#![cfg(feature = "realloc_trace")]
+extern crate std;
+
+use header_vec::*;
+use std::{string::String, sync::Mutex, vec::Vec};
+
+/// Captures the messages logged by `header_vec`.
+struct CaptureLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == "header_vec"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.messages
+                .lock()
+                .unwrap()
+                .push(std::format!("{}", record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger {
+    messages: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn test_realloc_trace() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let mut hv = HeaderVec::<_, u32>::new(());
+    for i in 0..5 {
+        hv.push(i);
+    }
+    hv.shrink_to_fit();
+
+    assert_eq!(
+        *LOGGER.messages.lock().unwrap(),
+        [
+            "HeaderVec reallocated from capacity 1 to 2",
+            "HeaderVec reallocated from capacity 2 to 4",
+            "HeaderVec reallocated from capacity 4 to 8",
+            "HeaderVec reallocated from capacity 8 to 5",
+        ]
+    );
+}
@@ -0,0 +1,62 @@
+extern crate std;
+
+use header_vec::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+
+/// Rounds every allocation up to whole pages, so that reallocating within a page always happens in
+/// place, and reallocating past it always moves the memory.
+struct PageAllocator;
+
+const PAGE: usize = 4096;
+
+fn page_layout(layout: Layout) -> Layout {
+    Layout::from_size_align(layout.size().div_ceil(PAGE) * PAGE, layout.align()).unwrap()
+}
+
+unsafe impl GlobalAlloc for PageAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(page_layout(layout))
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, page_layout(layout))
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        if page_layout(layout) == page_layout(new_layout) {
+            return ptr;
+        }
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PageAllocator = PageAllocator;
+
+#[test]
+fn test_reserve_detailed() {
+    let mut hv = HeaderVec::<_, u8>::with_capacity(8, ());
+    hv.push(1);
+
+    assert_eq!(hv.reserve_detailed(7), ReserveOutcome::NoGrowthNeeded);
+    assert_eq!(hv.capacity(), 8);
+
+    let before = hv.ptr();
+    assert_eq!(hv.reserve_detailed(100), ReserveOutcome::GrewInPlace);
+    assert!(hv.capacity() >= 101);
+    assert!(hv.is(before));
+
+    let before = hv.ptr();
+    assert_eq!(
+        hv.reserve_detailed(2 * PAGE),
+        ReserveOutcome::Moved { old: before }
+    );
+    assert!(!hv.is(before));
+    assert_eq!(hv, [1][..]);
+}
@@ -44,3 +44,1472 @@ fn test_head_array() {
         v_orig.as_slice().iter().copied().collect::<String>()
     );
 }
+
+#[test]
+fn test_eq_elements_only() {
+    let mut hv = HeaderVec::new(TestA { a: 1, b: 2, c: 3 });
+    hv.push(1);
+    hv.push(2);
+    hv.push(3);
+
+    assert_eq!(hv, [1, 2, 3][..]);
+    assert_eq!(hv, &[1, 2, 3][..]);
+    assert_eq!(hv, vec![1, 2, 3]);
+    assert_ne!(hv, [1, 2][..]);
+    assert_ne!(hv, &[1, 2, 4][..]);
+    assert_ne!(hv, vec![3, 2, 1]);
+}
+
+#[test]
+fn test_header_reserve() {
+    let mut hv = HeaderVec::<_, u32>::with_header_reserve(7u8, 100, 4);
+    for i in 0..10 {
+        hv.push(i);
+    }
+
+    let header_bytes = hv.as_slice().as_ptr() as usize - hv.ptr() as usize;
+    assert!(header_bytes >= 100);
+    assert_eq!(header_bytes % core::mem::size_of::<u32>(), 0);
+    assert_eq!(*hv, 7);
+    assert_eq!(hv, (0..10).collect::<Vec<u32>>());
+}
+
+#[test]
+#[should_panic(expected = "HeaderVec header reserve overflow")]
+fn test_header_reserve_overflow() {
+    let _ = HeaderVec::<(), u64>::with_header_reserve((), usize::MAX - 7, 1);
+}
+
+#[test]
+fn test_header_reserve_survives_growth() {
+    let mut hv = HeaderVec::<_, u16>::with_header_reserve(3u8, 33, 1);
+    let offset_bytes = hv.as_slice().as_ptr() as usize - hv.ptr() as usize;
+    assert!(offset_bytes >= 33);
+    for i in 0..100 {
+        hv.push(i);
+        assert_eq!(
+            hv.as_slice().as_ptr() as usize - hv.ptr() as usize,
+            offset_bytes
+        );
+    }
+    hv.shrink_to_fit();
+    assert_eq!(hv.capacity(), 100);
+    assert_eq!(
+        hv.as_slice().as_ptr() as usize - hv.ptr() as usize,
+        offset_bytes
+    );
+
+    hv.compact_fully();
+    assert!((hv.as_slice().as_ptr() as usize - hv.ptr() as usize) < offset_bytes);
+    assert_eq!(hv.as_slice(), (0..100).collect::<Vec<_>>());
+    hv.push(100);
+    assert_eq!(*hv, 3);
+}
+
+#[test]
+fn test_as_non_null() {
+    let mut hv = HeaderVec::<_, u8>::new(());
+    assert_eq!(hv.as_non_null().as_ptr() as *const (), hv.ptr());
+    for i in 0..100 {
+        hv.push(i);
+    }
+    assert_eq!(hv.as_non_null().as_ptr() as *const (), hv.ptr());
+}
+
+#[test]
+fn test_exact_growth() {
+    let mut hv = HeaderVec::<_, u32>::with_capacity_exact_growth(4, ());
+    for i in 0..4 {
+        hv.push(i);
+    }
+    assert_eq!(hv.capacity(), 4);
+    for i in 4..20 {
+        hv.push(i);
+        assert_eq!(hv.capacity(), i as usize + 1);
+    }
+    assert_eq!(hv, (0..20).collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_exact_growth_survives_clone() {
+    let mut hv = HeaderVec::<_, u32>::with_capacity_exact_growth(2, ());
+    hv.push(1);
+    hv.push(2);
+
+    let mut clone = hv.clone();
+    clone.push(3);
+    assert_eq!(clone.capacity(), 3);
+
+    let mut clone = hv.clone_with_extra_capacity(1);
+    clone.push(3);
+    clone.push(4);
+    assert_eq!(clone.capacity(), 4);
+
+    let mut clone = hv.clone_range(..1);
+    clone.push(2);
+    clone.push(3);
+    assert_eq!(clone.capacity(), 3);
+
+    let mut clone = hv.clone_copy();
+    clone.push(3);
+    assert_eq!(clone.capacity(), 3);
+}
+
+#[test]
+fn test_header_is_compact() {
+    // The header only holds the head, the capacity and the length, even with exact growth.
+    let words = if cfg!(feature = "atomic_append") {
+        3
+    } else {
+        2
+    };
+    let hv = HeaderVec::<(), u64>::with_capacity_exact_growth(4, ());
+    assert_eq!(
+        hv.as_slice().as_ptr() as usize - hv.ptr() as usize,
+        words * core::mem::size_of::<usize>()
+    );
+}
+
+#[test]
+fn test_clone_header() {
+    let mut hv = HeaderVec::new(TestA { a: 4, b: 5, c: 6 });
+    hv.push(1);
+
+    let mut head = hv.clone_header();
+    assert_eq!(head, *hv);
+    head.a = 10;
+    assert_eq!(head.a, 10);
+    assert_eq!(hv.a, 4);
+    assert_eq!(hv, [1][..]);
+}
+
+#[test]
+fn test_retain_mut_with_header() {
+    let mut hv = HeaderVec::new(0usize);
+    for i in 0..10 {
+        hv.push(i);
+    }
+
+    hv.retain_mut_with_header(|removed, e| {
+        if *e % 3 == 0 {
+            *removed += 1;
+            false
+        } else {
+            *e *= 10;
+            true
+        }
+    });
+
+    assert_eq!(*hv, 4);
+    assert_eq!(hv, [10, 20, 40, 50, 70, 80][..]);
+}
+
+#[test]
+fn test_retain_mut_with_header_panic_drops_once() {
+    use std::cell::RefCell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    struct Counted<'a>(usize, &'a RefCell<Vec<usize>>);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    let dropped = RefCell::new(Vec::new());
+    let mut hv = HeaderVec::new(());
+    for i in 0..8 {
+        hv.push(Counted(i, &dropped));
+    }
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        hv.retain_mut_with_header(|_, e| {
+            assert!(e.0 != 5);
+            e.0 % 2 == 1
+        })
+    }));
+    assert!(result.is_err());
+    drop(hv);
+
+    let mut dropped = dropped.into_inner();
+    dropped.sort();
+    let len = dropped.len();
+    dropped.dedup();
+    assert_eq!(dropped.len(), len);
+}
+
+#[test]
+fn test_shrink_to_fit() {
+    let mut hv = HeaderVec::<_, u32>::with_capacity(64, ());
+    hv.shrink_to_fit();
+    assert_eq!(hv.capacity(), 1);
+
+    for i in 0..10 {
+        hv.push(i);
+    }
+    assert_eq!(hv.capacity(), 16);
+    hv.shrink_to_fit();
+    assert_eq!(hv.capacity(), 10);
+    assert!(hv.shrink_to_fit().is_none());
+    assert_eq!(hv, (0..10).collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_from_fn() {
+    let hv = HeaderVec::from_fn('h', 5, |i| i * i);
+    assert_eq!(*hv, 'h');
+    assert_eq!(hv.capacity(), 5);
+    assert_eq!(hv, [0, 1, 4, 9, 16][..]);
+
+    let empty = HeaderVec::from_fn((), 0, |i| i);
+    assert!(empty.is_empty());
+}
+
+/// Run this under miri to check that pushing doesn't invalidate pointers it shouldn't.
+#[test]
+fn test_zero_push() {
+    let mut hv = HeaderVec::<_, u64>::new(TestA { a: 0, b: 0, c: 0 });
+    for i in 0..32 {
+        hv.push(0);
+        hv[i] = i as u64;
+        let last = hv.as_slice().last().unwrap() as *const u64;
+        assert_eq!(hv.len(), i + 1);
+        assert_eq!(unsafe { *last }, i as u64);
+    }
+    assert_eq!(hv, (0..32).collect::<Vec<u64>>());
+    assert_eq!(*hv, TestA { a: 0, b: 0, c: 0 });
+}
+
+/// Run this under miri to check that reading the length doesn't invalidate element pointers.
+#[test]
+fn test_len_with_live_slice() {
+    let mut hv = HeaderVec::<_, u32>::with_capacity(8, 1u8);
+    for i in 0..8 {
+        hv.push(i);
+    }
+
+    let slice = hv.as_slice();
+    assert_eq!(hv.len(), 8);
+    assert_eq!(hv.len_strict(), 8);
+    assert_eq!(slice[7], 7);
+
+    let elements = hv.as_mut_slice().as_mut_ptr();
+    assert_eq!(hv.len(), 8);
+    unsafe { *elements.add(3) = 30 };
+    assert_eq!(hv[3], 30);
+}
+
+#[test]
+fn test_reserve() {
+    let mut hv = HeaderVec::<_, u32>::new(());
+    assert!(hv.reserve(0).is_none());
+    hv.reserve(10);
+    assert_eq!(hv.capacity(), 10);
+    hv.push(1);
+    hv.reserve(10);
+    assert_eq!(hv.capacity(), 20);
+    assert_eq!(hv, [1][..]);
+}
+
+#[test]
+fn test_memory_report() {
+    let mut hv = HeaderVec::<_, u64>::with_header_reserve(1u8, 20, 10);
+    for i in 0..4 {
+        hv.push(i);
+    }
+
+    let report = hv.memory_report();
+    let element_offset = hv.as_slice().as_ptr() as usize - hv.ptr() as usize;
+    assert_eq!(report.header_bytes + report.padding_bytes, element_offset);
+    assert!(report.padding_bytes >= 20);
+    assert_eq!(report.element_bytes_used, 4 * 8);
+    assert_eq!(report.element_bytes_spare, 6 * 8);
+    assert_eq!(report.total_bytes, element_offset + 10 * 8);
+    assert_eq!(report.total_bytes, hv.allocation_size());
+    assert_eq!(
+        report.total_bytes,
+        report.header_bytes
+            + report.padding_bytes
+            + report.element_bytes_used
+            + report.element_bytes_spare
+    );
+}
+
+#[test]
+fn test_truncate() {
+    let mut hv = HeaderVec::<_, u8>::new(());
+    for i in 0..100 {
+        hv.push(i);
+    }
+    hv.truncate(200);
+    assert_eq!(hv.len(), 100);
+    hv.truncate(50);
+    assert_eq!(hv, (0..50).collect::<Vec<u8>>());
+    hv.truncate(10);
+    assert_eq!(hv, (0..10).collect::<Vec<u8>>());
+    assert_eq!(hv.capacity(), 128);
+
+    let drops = std::rc::Rc::new(());
+    let mut hv = HeaderVec::<_, std::rc::Rc<()>>::new(());
+    for _ in 0..10 {
+        hv.push(drops.clone());
+    }
+    hv.truncate(4);
+    assert_eq!(std::rc::Rc::strong_count(&drops), 5);
+    hv.truncate(0);
+    assert_eq!(std::rc::Rc::strong_count(&drops), 1);
+}
+
+#[test]
+fn test_repeat() {
+    let mut hv = HeaderVec::new('h');
+    hv.push(1);
+    hv.push(2);
+    hv.push(3);
+
+    let repeated = hv.repeat(2);
+    assert_eq!(*repeated, 'h');
+    assert_eq!(repeated.capacity(), 6);
+    assert_eq!(repeated, [1, 2, 3, 1, 2, 3][..]);
+    assert!(hv.repeat(0).is_empty());
+}
+
+#[test]
+fn test_swap_header_with() {
+    let mut a = HeaderVec::new("a");
+    a.push(1);
+    let mut b = HeaderVec::new("b");
+    b.push(2);
+    b.push(3);
+
+    a.swap_header_with(&mut b);
+    assert_eq!(*a, "b");
+    assert_eq!(*b, "a");
+    assert_eq!(a, [1][..]);
+    assert_eq!(b, [2, 3][..]);
+}
+
+#[test]
+fn test_extract_if() {
+    let mut hv = HeaderVec::new(());
+    for i in 0..10 {
+        hv.push(i);
+    }
+
+    let evens: Vec<i32> = hv.extract_if(|e| *e % 2 == 0).collect();
+    assert_eq!(evens, [0, 2, 4, 6, 8]);
+    assert_eq!(hv, [1, 3, 5, 7, 9][..]);
+}
+
+#[test]
+fn test_extract_if_partial() {
+    let mut hv = HeaderVec::new(());
+    for i in 0..10 {
+        hv.push(i.to_string());
+    }
+
+    let mut iter = hv.extract_if(|e| e.parse::<u32>().unwrap() % 3 == 0);
+    assert_eq!(iter.next().as_deref(), Some("0"));
+    assert_eq!(iter.next().as_deref(), Some("3"));
+    drop(iter);
+
+    assert_eq!(
+        hv.as_slice(),
+        ["1", "2", "4", "5", "6", "7", "8", "9"].map(String::from)
+    );
+}
+
+#[test]
+fn test_as_ptr_range() {
+    let mut hv = HeaderVec::<_, u16>::new(());
+    for i in 0..5 {
+        hv.push(i);
+    }
+
+    let range = hv.as_ptr_range();
+    assert_eq!(range.start, hv.as_ptr());
+    assert_eq!(range.end, unsafe { hv.as_ptr().add(5) });
+    assert!(range.contains(&(&hv[4] as *const u16)));
+    assert!(!range.contains(&range.end));
+}
+
+#[test]
+fn test_clone_from_weak() {
+    let mut hv = HeaderVec::new(TestA { a: 1, b: 2, c: 3 });
+    hv.push(10);
+    hv.push(20);
+
+    let weak = unsafe { hv.weak() };
+    let mut copy = unsafe { HeaderVec::clone_from_weak(&weak) };
+    assert_eq!(copy, hv);
+
+    copy.push(30);
+    copy.a = 5;
+    assert_eq!(hv, [10, 20][..]);
+    assert_eq!(hv.a, 1);
+    assert_eq!(copy, [10, 20, 30][..]);
+}
+
+#[test]
+fn test_stride() {
+    let hv = HeaderVec::from_fn((), 10, |i| i);
+    assert_eq!(hv.stride(2).copied().collect::<Vec<_>>(), [0, 2, 4, 6, 8]);
+    assert_eq!(hv.stride(3).copied().collect::<Vec<_>>(), [0, 3, 6, 9]);
+}
+
+#[test]
+#[should_panic]
+fn test_stride_zero() {
+    let hv = HeaderVec::from_fn((), 10, |i| i);
+    let _ = hv.stride(0);
+}
+
+#[test]
+fn test_header_and_slice_mut() {
+    let mut hv = HeaderVec::new(0);
+    hv.push(1);
+    hv.push(2);
+
+    let (head, slice) = hv.header_and_slice_mut();
+    for e in slice.iter_mut() {
+        *head += *e;
+        *e *= 3;
+    }
+    *head += slice.len();
+
+    assert_eq!(*hv, 5);
+    assert_eq!(hv, [3, 6][..]);
+}
+
+#[test]
+fn test_is_sorted() {
+    let sorted = HeaderVec::from_fn((), 5, |i| i);
+    assert!(sorted.is_sorted());
+    assert!(!sorted.is_sorted_by(|a, b| a >= b));
+
+    let unsorted = HeaderVec::from_fn((), 5, |i| 5 - i);
+    assert!(!unsorted.is_sorted());
+    assert!(unsorted.is_sorted_by(|a, b| a >= b));
+
+    let single = HeaderVec::from_fn((), 1, |i| i);
+    assert!(single.is_sorted());
+    assert!(single.is_sorted_by(|_, _| false));
+}
+
+#[test]
+fn test_try_from_iter_with_header() {
+    let hv = HeaderVec::try_from_iter_with_header('h', (0..4).map(Ok::<_, ()>)).unwrap();
+    assert_eq!(*hv, 'h');
+    assert_eq!(hv, [0, 1, 2, 3][..]);
+
+    let drops = std::rc::Rc::new(());
+    let items = (0..10).map(|i| if i == 5 { Err(i) } else { Ok(drops.clone()) });
+    let result = HeaderVec::try_from_iter_with_header(drops.clone(), items);
+    assert_eq!(result.err(), Some(5));
+    assert_eq!(std::rc::Rc::strong_count(&drops), 1);
+}
+
+#[test]
+fn test_retain_and_maybe_shrink() {
+    let mut hv = HeaderVec::from_fn((), 100, |i| i);
+    hv.retain_and_maybe_shrink(|&e| e % 10 == 0);
+    assert_eq!(hv.capacity(), 20);
+    assert_eq!(hv, [0, 10, 20, 30, 40, 50, 60, 70, 80, 90][..]);
+
+    hv.retain_and_maybe_shrink(|&e| e != 0);
+    assert_eq!(hv.capacity(), 20);
+    assert_eq!(hv.len(), 9);
+}
+
+#[test]
+fn test_uninit_header() {
+    let mut uninit = HeaderVec::<TestA, u32>::with_capacity_uninit_header(4);
+    uninit.header_mut().write(TestA { a: 1, b: 2, c: 3 });
+    let mut hv = unsafe { uninit.assume_header_init() };
+
+    for i in 0..10 {
+        hv.push(i);
+    }
+    assert_eq!(*hv, TestA { a: 1, b: 2, c: 3 });
+    assert_eq!(hv, (0..10).collect::<Vec<u32>>());
+
+    // Dropping it without initializing the header must not drop the header.
+    let uninit = HeaderVec::<String, u32>::with_capacity_uninit_header(4);
+    drop(uninit);
+}
+
+#[test]
+#[should_panic(expected = "HeaderVec does not support zero-sized element types T")]
+fn test_zero_sized_elements() {
+    assert!(HeaderVec::<(), u8>::element_size_nonzero());
+    assert!(!HeaderVec::<u8, ()>::element_size_nonzero());
+    HeaderVec::<u8, ()>::with_header_reserve(0, 16, 1);
+}
+
+#[test]
+fn test_zero_sized_elements_slice() {
+    #[derive(Debug, PartialEq)]
+    struct Zst;
+
+    let mut hv = HeaderVec::<_, Zst>::new(TestA { a: 1, b: 2, c: 3 });
+    let ptr = hv.ptr();
+    for _ in 0..1000 {
+        assert!(hv.push(Zst).is_none());
+    }
+    assert!(hv.is(ptr));
+    assert_eq!(hv.len(), 1000);
+    assert_eq!(hv.as_slice().iter().count(), 1000);
+    assert_eq!(hv.as_ptr() as usize % core::mem::align_of::<Zst>(), 0);
+    assert_eq!(*hv, TestA { a: 1, b: 2, c: 3 });
+    hv.retain(|_| false);
+    assert!(hv.is_empty());
+}
+
+#[test]
+fn test_zero_sized_elements_no_realloc() {
+    let mut hv = HeaderVec::<_, ()>::new('h');
+    for _ in 0..10 {
+        hv.push(());
+    }
+    assert!(hv.set_capacity(10).is_none());
+    assert!(hv.shrink_to_fit().is_none());
+    assert!(hv.compact_fully().is_none());
+    assert!(hv.auto_shrink().is_none());
+    assert_eq!(hv.shrink_to_fit_reporting(), (None, 0));
+    assert_eq!(hv.capacity(), usize::MAX);
+    assert_eq!(hv.len(), 10);
+}
+
+#[test]
+fn test_set_capacity() {
+    let mut hv = HeaderVec::from_fn((), 5, |i| i);
+    hv.set_capacity(37);
+    assert_eq!(hv.capacity(), 37);
+    hv.set_capacity(5);
+    assert_eq!(hv.capacity(), 5);
+    assert!(hv.set_capacity(5).is_none());
+    assert_eq!(hv, [0, 1, 2, 3, 4][..]);
+}
+
+#[test]
+#[should_panic(expected = "HeaderVec capacity cannot be less than its length")]
+fn test_set_capacity_below_len() {
+    let mut hv = HeaderVec::from_fn((), 5, |i| i);
+    hv.set_capacity(4);
+}
+
+#[test]
+fn test_for_each_mut_with_header() {
+    let mut hv = HeaderVec::from_fn(2.5f32, 4, |i| i as f32);
+    hv.for_each_mut_with_header(|scale, e| *e *= *scale);
+    assert_eq!(hv, [0.0, 2.5, 5.0, 7.5][..]);
+}
+
+#[test]
+fn test_bytes_for_capacity() {
+    for capacity in [1, 2, 7, 64, 1000] {
+        let hv = HeaderVec::<TestA, u16>::with_capacity(capacity, TestA { a: 0, b: 0, c: 0 });
+        assert_eq!(
+            HeaderVec::<TestA, u16>::bytes_for_capacity(capacity),
+            hv.allocation_size()
+        );
+    }
+}
+
+#[test]
+fn test_clone_with_extra_capacity() {
+    let hv = HeaderVec::from_fn('h', 3, |i| i);
+    let mut clone = hv.clone_with_extra_capacity(5);
+    assert_eq!(clone, hv);
+    assert_eq!(clone.capacity(), 8);
+
+    let ptr = clone.ptr();
+    for i in 3..8 {
+        assert!(clone.push(i).is_none());
+    }
+    assert!(clone.is(ptr));
+    assert_eq!(clone, (0..8).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_swap_element_with() {
+    let mut a = HeaderVec::from_fn("a", 3, |i| i);
+    let mut b = HeaderVec::from_fn("b", 2, |i| i + 10);
+
+    a.swap_element_with(0, &mut b, 1);
+    assert_eq!(a, [11, 1, 2][..]);
+    assert_eq!(b, [10, 0][..]);
+    assert_eq!(*a, "a");
+    assert_eq!(*b, "b");
+}
+
+#[test]
+#[should_panic]
+fn test_swap_element_with_out_of_bounds() {
+    let mut a = HeaderVec::from_fn((), 3, |i| i);
+    let mut b = HeaderVec::from_fn((), 2, |i| i);
+    a.swap_element_with(0, &mut b, 2);
+}
+
+#[test]
+fn test_compact_and_reserve() {
+    let mut hv = HeaderVec::from_fn((), 64, |i| i);
+    hv.push(64);
+    assert_eq!(hv.capacity(), 128);
+    hv.retain(|&e| e < 10);
+
+    hv.compact_and_reserve(6);
+    assert_eq!(hv.capacity(), 16);
+    assert_eq!(hv, (0..10).collect::<Vec<_>>());
+
+    hv.compact_and_reserve(100);
+    assert_eq!(hv.capacity(), 110);
+}
+
+#[test]
+fn test_into_chunks() {
+    let hv = HeaderVec::from_fn('h', 10, |i| i.to_string());
+    let chunks = hv.into_chunks(4);
+    assert_eq!(
+        chunks.iter().map(|c| c.len()).collect::<Vec<_>>(),
+        [4, 4, 2]
+    );
+    assert!(chunks.iter().all(|c| **c == 'h'));
+    assert_eq!(
+        chunks
+            .iter()
+            .flat_map(|c| c.as_slice().iter().cloned())
+            .collect::<Vec<_>>(),
+        (0..10).map(|i| i.to_string()).collect::<Vec<_>>()
+    );
+
+    assert!(HeaderVec::<_, u8>::new(()).into_chunks(4).is_empty());
+}
+
+#[test]
+fn test_drain() {
+    let mut hv = HeaderVec::from_fn((), 10, |i| i);
+    assert_eq!(hv.drain(2..5).collect::<Vec<_>>(), [2, 3, 4]);
+    assert_eq!(hv, [0, 1, 5, 6, 7, 8, 9][..]);
+
+    let mut drain = hv.drain(1..=4);
+    assert_eq!(drain.len(), 4);
+    assert_eq!(drain.next_back(), Some(7));
+    assert_eq!(drain.next(), Some(1));
+    drop(drain);
+    assert_eq!(hv, [0, 8, 9][..]);
+}
+
+#[test]
+fn test_drain_drops_remaining() {
+    let drops = std::rc::Rc::new(());
+    let mut hv = HeaderVec::from_fn((), 10, |_| drops.clone());
+    let mut drain = hv.drain(..8);
+    drain.next();
+    drop(drain);
+    assert_eq!(hv.len(), 2);
+    assert_eq!(std::rc::Rc::strong_count(&drops), 3);
+}
+
+#[test]
+fn test_drain_then_shrink_to_fit() {
+    let mut hv = HeaderVec::from_fn((), 100, |i| i);
+    hv.drain(10..);
+    hv.shrink_to_fit();
+    assert_eq!(hv.capacity(), 10);
+    assert_eq!(hv, (0..10).collect::<Vec<_>>());
+
+    hv.drain(..);
+    hv.shrink_to_fit();
+    assert_eq!(hv.capacity(), 1);
+    assert!(hv.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn test_drain_out_of_range() {
+    let mut hv = HeaderVec::from_fn((), 10, |i| i);
+    hv.drain(5..11);
+}
+
+#[test]
+fn test_as_array() {
+    let hv = HeaderVec::from_fn((), 3, |i| i);
+    assert_eq!(hv.as_array::<3>(), Some(&[0, 1, 2]));
+    assert_eq!(hv.as_array::<2>(), None);
+    assert_eq!(hv.as_array::<4>(), None);
+}
+
+#[test]
+fn test_header_eq_and_elements_eq() {
+    let a = HeaderVec::from_fn('a', 3, |i| i);
+    let b = HeaderVec::from_fn('a', 3, |i| i + 1);
+    assert!(a.header_eq(&b));
+    assert!(!a.elements_eq(&b));
+    assert_ne!(a, b);
+
+    let c = HeaderVec::from_fn('c', 3, |i| i);
+    assert!(!a.header_eq(&c));
+    assert!(a.elements_eq(&c));
+    assert_ne!(a, c);
+}
+
+#[test]
+fn test_iter_element_ptrs() {
+    let hv = HeaderVec::from_fn((), 5, |i| i * 2);
+    let ptrs = hv.iter_element_ptrs().collect::<Vec<_>>();
+    assert_eq!(ptrs.len(), 5);
+    for (ptr, e) in ptrs.iter().zip(hv.as_slice()) {
+        assert_eq!(*ptr, e as *const usize);
+        assert_eq!(unsafe { **ptr }, *e);
+    }
+}
+
+#[test]
+fn test_truncate_returning() {
+    let drops = std::rc::Rc::new(());
+    let mut hv = HeaderVec::from_fn((), 6, |i| (i, drops.clone()));
+
+    let tail = hv.truncate_returning(4);
+    assert_eq!(
+        hv.as_slice().iter().map(|e| e.0).collect::<Vec<_>>(),
+        [0, 1, 2, 3]
+    );
+    assert_eq!(tail.iter().map(|e| e.0).collect::<Vec<_>>(), [4, 5]);
+    assert_eq!(std::rc::Rc::strong_count(&drops), 7);
+
+    assert!(hv.truncate_returning(10).is_empty());
+    drop(tail);
+    assert_eq!(std::rc::Rc::strong_count(&drops), 5);
+    drop(hv);
+    assert_eq!(std::rc::Rc::strong_count(&drops), 1);
+}
+
+#[test]
+fn test_fmt_write() {
+    use core::fmt::Write;
+
+    let mut hv = HeaderVec::<(), u8>::new(());
+    write!(hv, "{} + {} = {}", 1, 2.5, 3.5).unwrap();
+    hv.write_char('!').unwrap();
+    assert_eq!(hv.as_slice(), b"1 + 2.5 = 3.5!");
+}
+
+#[test]
+fn test_with_capacity_zero() {
+    let mut hv = HeaderVec::<_, u32>::with_capacity(0, 'h');
+    assert!(hv.is_empty());
+    assert_eq!(hv.capacity(), 1);
+    assert_eq!(hv.clone(), hv);
+    hv.push(1);
+    hv.push(2);
+    assert_eq!(hv, [1, 2][..]);
+}
+
+#[test]
+fn test_clone_range() {
+    let hv = HeaderVec::from_fn('h', 6, |i| i.to_string());
+    let strings = |range: core::ops::Range<usize>| range.map(|i| i.to_string()).collect::<Vec<_>>();
+
+    let prefix = hv.clone_range(..2);
+    assert_eq!(prefix, strings(0..2));
+    assert_eq!(prefix.capacity(), 2);
+    assert_eq!(*prefix, 'h');
+    assert_eq!(hv.clone_range(4..), strings(4..6));
+    assert_eq!(hv.clone_range(1..=3), strings(1..4));
+    assert_eq!(hv, strings(0..6));
+}
+
+#[test]
+#[should_panic]
+fn test_clone_range_out_of_range() {
+    let hv = HeaderVec::from_fn((), 6, |i| i);
+    hv.clone_range(3..7);
+}
+
+#[test]
+fn test_new_with_min_capacity() {
+    let mut hv = HeaderVec::new_with_min_capacity((), 8);
+    let ptr = hv.ptr();
+    for i in 0..8 {
+        hv.push(i);
+    }
+    assert!(hv.is(ptr));
+    assert_eq!(hv.capacity(), 8);
+}
+
+#[test]
+fn test_compact_fully() {
+    let mut hv = HeaderVec::<_, u32>::with_header_reserve('h', 256, 16);
+    for i in 0..10 {
+        hv.push(i);
+    }
+    let before = hv.allocation_size();
+
+    hv.compact_fully();
+    assert_eq!(hv.capacity(), 10);
+    assert_eq!(
+        hv.allocation_size(),
+        HeaderVec::<char, u32>::bytes_for_capacity(10)
+    );
+    assert!(hv.allocation_size() < before - 256);
+    assert_eq!(*hv, 'h');
+    assert_eq!(hv, (0..10).collect::<Vec<u32>>());
+    assert!(hv.compact_fully().is_none());
+    assert!(hv.auto_shrink().is_none());
+    assert_eq!(hv.shrink_to_fit_reporting(), (None, 0));
+
+    hv.push(10);
+    assert_eq!(hv, (0..11).collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_as_nonempty_slice() {
+    let mut hv = HeaderVec::<_, u8>::new(());
+    assert_eq!(hv.as_nonempty_slice(), None);
+    hv.push(3);
+    assert_eq!(hv.as_nonempty_slice(), Some(&[3][..]));
+}
+
+#[test]
+fn test_freeze() {
+    let frozen = HeaderVec::from_fn(TestA { a: 1, b: 2, c: 3 }, 100, |i| i).freeze();
+    assert_eq!(frozen.header().a, 1);
+
+    let sums = std::thread::scope(|s| {
+        let handles = (0..4)
+            .map(|t| {
+                let frozen = &frozen;
+                s.spawn(move || frozen.iter().map(|e| e * t).sum::<usize>())
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(sums, [0, 4950, 9900, 14850]);
+    assert_eq!(frozen.len(), 100);
+}
+
+#[test]
+fn test_extend_exact() {
+    let mut hv = HeaderVec::<_, u32>::new(());
+    hv.push(0);
+    hv.extend_exact(1..38);
+    assert_eq!(hv.capacity(), 38);
+    assert_eq!(hv, (0..38).collect::<Vec<u32>>());
+
+    hv.reserve_exact(2);
+    assert_eq!(hv.capacity(), 40);
+    assert!(hv.extend_exact([38, 39]).is_none());
+    assert_eq!(hv.capacity(), 40);
+}
+
+#[test]
+fn test_header_and_spare_mut() {
+    let mut hv = HeaderVec::<_, String>::with_capacity(4, 0);
+    hv.push("a".into());
+
+    let (written, spare) = hv.header_and_spare_mut();
+    assert_eq!(spare.len(), 3);
+    for (i, slot) in spare.iter_mut().take(2).enumerate() {
+        slot.write(i.to_string());
+        *written += 1;
+    }
+    unsafe { hv.set_len(3) };
+
+    assert_eq!(*hv, 2);
+    assert_eq!(hv.as_slice(), ["a", "0", "1"]);
+}
+
+#[test]
+fn test_clone_copy() {
+    let hv = HeaderVec::from_fn(TestA { a: 1, b: 2, c: 3 }, 1000, |i| i as u32);
+    let copy = hv.clone_copy();
+    assert_eq!(copy, hv);
+    assert_eq!(copy.capacity(), 1000);
+    assert_ne!(copy.ptr(), hv.ptr());
+
+    let empty = HeaderVec::<_, u8>::new(()).clone_copy();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_spare_capacity_bytes() {
+    let mut hv = HeaderVec::<_, u64>::with_capacity(10, ());
+    hv.push(1);
+    assert_eq!(hv.spare_capacity(), 9);
+    assert_eq!(hv.spare_capacity_bytes(), 9 * 8);
+
+    let mut bytes = HeaderVec::<_, u8>::with_capacity(10, ());
+    bytes.push(1);
+    assert_eq!(bytes.spare_capacity_bytes(), bytes.spare_capacity());
+}
+
+#[test]
+fn test_clone_into_vec() {
+    let a = HeaderVec::from_fn((), 3, |i| i.to_string());
+    let b = HeaderVec::from_fn((), 2, |i| (i + 10).to_string());
+    let mut out = vec![String::from("start")];
+    a.clone_into_vec(&mut out);
+    b.clone_into_vec(&mut out);
+    assert_eq!(out, ["start", "0", "1", "2", "10", "11"]);
+    assert_eq!(a.len(), 3);
+}
+
+#[test]
+fn test_clear_and_reset_header() {
+    let mut hv = HeaderVec::from_fn(String::from("used"), 20, |i| i);
+    let capacity = hv.capacity();
+    hv.clear_and_reset_header();
+    assert!(hv.is_empty());
+    assert_eq!(*hv, "");
+    assert_eq!(hv.capacity(), capacity);
+
+    hv.push(1);
+    hv.clear();
+    assert!(hv.is_empty());
+}
+
+#[test]
+fn test_debug_raw_parts() {
+    let hv = HeaderVec::from_fn((), 5, |i| i);
+    let (ptr, len, capacity) = hv.debug_raw_parts();
+    assert_eq!(ptr, hv.ptr());
+    assert_eq!(len, hv.len());
+    assert_eq!(capacity, hv.capacity());
+}
+
+#[test]
+fn test_extend_updating_weak() {
+    let mut hv = HeaderVec::<_, u32>::new(TestA { a: 1, b: 2, c: 3 });
+    let mut weak = unsafe { hv.weak() };
+
+    let old = unsafe { hv.extend_updating_weak(&mut weak, 0..1) };
+    assert!(old.is_none());
+    assert!(weak.is(hv.ptr()));
+
+    unsafe { hv.extend_updating_weak(&mut weak, 1..1000) };
+    assert!(weak.is(hv.ptr()));
+    assert_eq!(*weak, hv);
+    assert_eq!(weak.len(), 1000);
+}
+
+#[test]
+fn test_contains_ptr() {
+    let hv = HeaderVec::from_fn((), 4, |i| i as u32);
+    let other = HeaderVec::from_fn((), 4, |i| i as u32);
+    let start = hv.as_ptr();
+
+    assert!(hv.contains_ptr(start));
+    assert!(hv.contains_ptr(&hv[3]));
+    assert!(!hv.contains_ptr(unsafe { start.add(4) }));
+    assert!(!hv.contains_ptr(unsafe { start.sub(1) }));
+    assert!(!hv.contains_ptr(&other[0]));
+}
+
+#[test]
+fn test_join_with() {
+    let hv = HeaderVec::join_with(&[&[1, 2], &[3], &[4, 5, 6]], &[0, 0], 'h');
+    assert_eq!(*hv, 'h');
+    assert_eq!(hv, [1, 2, 0, 0, 3, 0, 0, 4, 5, 6][..]);
+    assert_eq!(hv.capacity(), 10);
+
+    assert!(HeaderVec::<_, u8>::join_with(&[], &[0], ()).is_empty());
+}
+
+#[test]
+fn test_auto_shrink() {
+    let mut hv = HeaderVec::<_, u32>::with_capacity(100, ());
+    for i in 0..5 {
+        hv.push(i);
+    }
+    hv.auto_shrink();
+    assert_eq!(hv.capacity(), 8);
+    assert_eq!(hv, [0, 1, 2, 3, 4][..]);
+
+    let mut full = HeaderVec::from_fn((), 50, |i| i);
+    full.reserve_exact(10);
+    assert!(full.auto_shrink().is_none());
+    assert_eq!(full.capacity(), 60);
+}
+
+#[test]
+fn test_extend_from_slice() {
+    let mut hv = HeaderVec::from_fn((), 2, |i| i.to_string());
+    hv.extend_from_slice(&[String::from("a"), String::from("b")]);
+    assert_eq!(hv.as_slice(), ["0", "1", "a", "b"]);
+}
+
+#[test]
+fn test_extend_from_within() {
+    let mut hv = HeaderVec::from_fn((), 5, |i| i.to_string());
+    assert_eq!(hv.capacity(), 5);
+
+    // Extending crosses the capacity, so the source is read after the elements moved.
+    hv.extend_from_within(1..4);
+    assert_eq!(hv.as_slice(), ["0", "1", "2", "3", "4", "1", "2", "3"]);
+    hv.extend_from_within(..);
+    assert_eq!(hv.len(), 16);
+    assert_eq!(hv.as_slice()[8..], hv.as_slice()[..8]);
+    hv.extend_from_within(3..3);
+    assert_eq!(hv.len(), 16);
+}
+
+#[test]
+#[should_panic]
+fn test_extend_from_within_out_of_bounds() {
+    let mut hv = HeaderVec::from_fn((), 3, |i| i);
+    hv.extend_from_within(2..4);
+}
+
+#[test]
+fn test_into_iter() {
+    let hv = HeaderVec::from_fn('h', 5, |i| i);
+    let mut iter = hv.into_iter();
+    assert_eq!(*iter.header(), 'h');
+    assert_eq!(iter.len(), 5);
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next_back(), Some(4));
+    assert_eq!(iter.as_slice(), [1, 2, 3]);
+    assert_eq!(iter.rev().collect::<Vec<_>>(), [3, 2, 1]);
+}
+
+#[test]
+fn test_into_iter_drops_once() {
+    use std::cell::RefCell;
+
+    struct Counted<'a>(usize, &'a RefCell<Vec<usize>>);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    let dropped = RefCell::new(Vec::new());
+    let hv = HeaderVec::from_fn((), 8, |i| Counted(i, &dropped));
+    let mut iter = hv.into_iter();
+    drop(iter.next());
+    drop(iter.next());
+    drop(iter.next_back());
+    assert_eq!(*dropped.borrow(), [0, 1, 7]);
+    drop(iter);
+
+    let mut dropped = dropped.into_inner();
+    dropped.sort();
+    assert_eq!(dropped, (0..8).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_with_capacity_assume_len() {
+    let mut hv = unsafe { HeaderVec::<_, u64>::with_capacity_assume_len(8, 5, 'h') };
+    assert_eq!(hv.len(), 5);
+    assert!(hv.capacity() >= 8);
+    let ptr = hv.as_mut_ptr();
+    for i in 0..5 {
+        unsafe { ptr.add(i).write(i as u64 * 10) };
+    }
+    assert_eq!(hv.as_slice(), [0, 10, 20, 30, 40]);
+    assert_eq!(*hv, 'h');
+}
+
+#[test]
+fn test_try_index() {
+    let hv = HeaderVec::from_fn((), 3, |i| i);
+    assert_eq!(hv.try_index(2), Ok(&2));
+    let err = hv.try_index(7).unwrap_err();
+    assert_eq!(err, IndexError { index: 7, len: 3 });
+    assert_eq!(
+        err.to_string(),
+        "index 7 is out of bounds for a HeaderVec of length 3"
+    );
+}
+
+#[test]
+fn test_from_elem() {
+    let hv = HeaderVec::from_elem('h', 0u8, 16);
+    assert_eq!(hv.len(), 16);
+    assert!(hv.as_slice().iter().all(|&x| x == 0));
+    assert_eq!(*hv, 'h');
+
+    let empty = HeaderVec::from_elem((), String::from("x"), 0);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_weak_element_access() {
+    let hv = HeaderVec::from_fn((), 4, |i| i * 2);
+    let weak = unsafe { hv.weak() };
+    assert_eq!(weak.len(), 4);
+    assert!(!weak.is_empty());
+    assert_eq!(weak.as_slice(), [0, 2, 4, 6]);
+    assert_eq!(weak.get(3), Some(&6));
+    assert_eq!(weak.get(4), None);
+}
+
+#[test]
+fn test_rotate_copy() {
+    let expected: Vec<u32> = (0..100).collect();
+    for amount in [0, 1, 5, 63, 64, 65, 99, 100] {
+        let mut hv = HeaderVec::from_fn((), 100, |i| i as u32);
+        let mut slice = expected.clone();
+        hv.rotate_left_copy(amount);
+        slice.rotate_left(amount);
+        assert_eq!(hv.as_slice(), slice);
+
+        let mut hv = HeaderVec::from_fn((), 100, |i| i as u32);
+        let mut slice = expected.clone();
+        hv.rotate_right_copy(amount);
+        slice.rotate_right(amount);
+        assert_eq!(hv.as_slice(), slice);
+    }
+}
+
+#[test]
+fn test_next_capacity() {
+    for (initial, additional) in [(1, 1), (4, 3), (4, 5), (4, 20), (16, 0)] {
+        let mut hv = HeaderVec::<(), u32>::with_capacity(initial, ());
+        let predicted = HeaderVec::<(), u32>::next_capacity(hv.capacity(), additional, false);
+        hv.reserve(additional);
+        assert_eq!(hv.capacity(), predicted);
+
+        let mut hv = HeaderVec::<(), u32>::with_capacity_exact_growth(initial, ());
+        let predicted = HeaderVec::<(), u32>::next_capacity(hv.capacity(), additional, true);
+        hv.reserve(additional);
+        assert_eq!(hv.capacity(), predicted);
+    }
+}
+
+#[test]
+fn test_next_capacity_saturates() {
+    let max = usize::MAX / 2 + 1;
+    assert_eq!(
+        HeaderVec::<(), u8>::next_capacity(max, max + 1, false),
+        usize::MAX
+    );
+}
+
+#[test]
+fn test_dedup_counting() {
+    let mut hv = HeaderVec::<(), String>::new(());
+    for s in ["a", "a", "b", "c", "c", "c", "a", "d", "d"] {
+        hv.push(s.to_string());
+    }
+    assert_eq!(hv.dedup_counting(), 4);
+    assert_eq!(hv.as_slice(), ["a", "b", "c", "a", "d"]);
+    assert_eq!(hv.dedup_counting(), 0);
+
+    let mut hv = HeaderVec::from_fn((), 6, |i| i / 3);
+    hv.dedup();
+    assert_eq!(hv.as_slice(), [0, 1]);
+}
+
+#[test]
+fn test_header_and_iter_mut() {
+    let mut hv = HeaderVec::from_fn(0, 4, |i| i + 1);
+    let (sum, iter) = hv.header_and_iter_mut();
+    for e in iter {
+        *sum += *e;
+        *e *= 2;
+    }
+    assert_eq!(*hv, 10);
+    assert_eq!(hv.as_slice(), [2, 4, 6, 8]);
+}
+
+#[test]
+fn test_shrink_to_fit_reporting() {
+    let mut hv = HeaderVec::<(), u64>::with_capacity(1000, ());
+    hv.extend_from_slice(&[1, 2, 3]);
+    let expected = HeaderVec::<(), u64>::bytes_for_capacity(1000)
+        - HeaderVec::<(), u64>::bytes_for_capacity(3);
+    let (_, reclaimed) = hv.shrink_to_fit_reporting();
+    assert_eq!(reclaimed, expected);
+    assert_eq!(hv.capacity(), 3);
+    assert_eq!(hv.shrink_to_fit_reporting(), (None, 0));
+}
+
+#[test]
+fn test_new_cloned_header() {
+    let head = String::from("header");
+    let hv = HeaderVec::<String, u8>::new_cloned_header(&head);
+    assert_eq!(*hv, head);
+    assert!(hv.is_empty());
+}
+
+#[test]
+fn test_push_array() {
+    let mut hv = HeaderVec::<(), u32>::new(());
+    hv.push(0);
+    hv.push_array([1, 2, 3]);
+    assert_eq!(hv.as_slice(), [0, 1, 2, 3]);
+    hv.push_array([]);
+    assert_eq!(hv.len(), 4);
+
+    let mut hv = HeaderVec::<(), String>::new(());
+    hv.push_array([String::from("a"), String::from("b")]);
+    assert_eq!(hv.as_slice(), ["a", "b"]);
+}
+
+#[test]
+fn test_map_header_ref() {
+    let hv = HeaderVec::<TestA, u8>::new(TestA { a: 1, b: 2, c: 3 });
+    assert_eq!(*hv.map_header_ref(|h| &h.b), 2);
+}
+
+#[test]
+fn test_pairs() {
+    let hv = HeaderVec::from_fn((), 4, |i| i * i);
+    let deltas: Vec<_> = hv.pairs().map(|(a, b)| b - a).collect();
+    assert_eq!(deltas, [1, 3, 5]);
+
+    let hv = HeaderVec::<(), usize>::new(());
+    assert_eq!(hv.pairs().count(), 0);
+    let hv = HeaderVec::from_fn((), 1, |i| i);
+    assert_eq!(hv.pairs().count(), 0);
+}
+
+#[test]
+fn test_with_capacity_zeroed_len() {
+    let hv = unsafe { HeaderVec::<_, u64>::with_capacity_zeroed_len(1000, 'h') };
+    assert_eq!(hv.len(), 1000);
+    assert!(hv.as_slice().iter().all(|&x| x == 0));
+    assert_eq!(*hv, 'h');
+
+    let hv = unsafe { HeaderVec::<(), u64>::with_capacity_zeroed_len(0, ()) };
+    assert!(hv.is_empty());
+}
+
+#[test]
+fn test_into_array() {
+    let hv = HeaderVec::from_fn(String::from("h"), 3, |i| i.to_string());
+    let (array, head) = hv.into_array::<3>().unwrap();
+    assert_eq!(array, ["0", "1", "2"]);
+    assert_eq!(head, "h");
+
+    let hv = HeaderVec::from_fn(String::from("h"), 3, |i| i.to_string());
+    let hv = hv.into_array::<4>().unwrap_err();
+    assert_eq!(hv.as_slice(), ["0", "1", "2"]);
+    assert_eq!(*hv, "h");
+}
+
+#[test]
+fn test_push_reserving() {
+    let mut hv = HeaderVec::<(), usize>::new(());
+    hv.push(0);
+    hv.push_reserving(1, 100);
+    assert!(hv.capacity() >= 101);
+    let capacity = hv.capacity();
+    for i in 2..100 {
+        hv.push_reserving(i, 100 - i);
+    }
+    assert_eq!(hv.capacity(), capacity);
+    assert_eq!(hv.as_slice(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_swap_remove_front() {
+    let mut hv = HeaderVec::from_fn((), 5, |i| i);
+    assert_eq!(hv.swap_remove_front(), Some(0));
+    assert_eq!(hv.as_slice(), [4, 1, 2, 3]);
+    assert_eq!(hv.swap_remove(1), 1);
+    assert_eq!(hv.as_slice(), [4, 3, 2]);
+    assert_eq!(hv.pop(), Some(2));
+    assert_eq!(hv.swap_remove_front(), Some(4));
+    assert_eq!(hv.swap_remove_front(), Some(3));
+    assert_eq!(hv.swap_remove_front(), None);
+    assert_eq!(hv.pop(), None);
+}
+
+#[test]
+fn test_retain_partial() {
+    let mut hv = HeaderVec::from_fn((), 6, |i| i);
+    hv.retain(|_| true);
+    assert_eq!(hv.as_slice(), [0, 1, 2, 3, 4, 5]);
+    hv.retain(|&x| x != 3 && x != 5);
+    assert_eq!(hv.as_slice(), [0, 1, 2, 4]);
+    hv.retain(|&x| x != 0);
+    assert_eq!(hv.as_slice(), [1, 2, 4]);
+}
+
+#[test]
+fn test_retain_panic_drops_once() {
+    use std::cell::RefCell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    struct Counted<'a>(usize, &'a RefCell<Vec<usize>>);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    let dropped = RefCell::new(Vec::new());
+    let mut hv = HeaderVec::from_fn((), 8, |i| Counted(i, &dropped));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        hv.retain(|e| {
+            assert!(e.0 != 5);
+            e.0 % 2 == 1
+        })
+    }));
+    assert!(result.is_err());
+    drop(hv);
+
+    let mut dropped = dropped.into_inner();
+    dropped.sort();
+    let len = dropped.len();
+    dropped.dedup();
+    assert_eq!(dropped.len(), len);
+}
+
+#[test]
+fn test_into_header_and_vec() {
+    let drops = std::rc::Rc::new(());
+    let hv = HeaderVec::from_fn(drops.clone(), 3, |_| drops.clone());
+    assert_eq!(std::rc::Rc::strong_count(&drops), 5);
+    let (head, elements) = hv.into_header_and_vec();
+    assert!(std::rc::Rc::ptr_eq(&head, &drops));
+    assert_eq!(elements.len(), 3);
+    assert_eq!(std::rc::Rc::strong_count(&drops), 5);
+    drop(head);
+    drop(elements);
+    assert_eq!(std::rc::Rc::strong_count(&drops), 1);
+}
+
+#[test]
+fn test_over_aligned_elements() {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(align(64))]
+    struct OverAligned(u8);
+
+    let mut hv = HeaderVec::<u8, OverAligned>::new(1);
+    for i in 0..10 {
+        hv.push(OverAligned(i));
+        assert_eq!(hv.as_ptr() as usize % 64, 0);
+    }
+    assert_eq!(hv.as_slice()[9], OverAligned(9));
+
+    let mut hv = HeaderVec::<(u8, u8, u8), OverAligned>::with_capacity(3, (1, 2, 3));
+    hv.push(OverAligned(7));
+    assert_eq!(hv.as_ptr() as usize % 64, 0);
+    assert_eq!(hv.as_slice(), [OverAligned(7)]);
+}
+
+#[test]
+fn test_extend_cloned() {
+    let other = HeaderVec::from_fn((), 10, |i| i.to_string());
+    let mut hv = HeaderVec::<(), String>::new(());
+    hv.push(String::from("start"));
+    hv.extend_cloned(
+        other
+            .as_slice()
+            .iter()
+            .filter(|s| s.len() == 1 && s.as_str() < "4"),
+    );
+    assert_eq!(hv.as_slice(), ["start", "0", "1", "2", "3"]);
+    assert_eq!(other.len(), 10);
+}
+
+#[test]
+fn test_pop_front() {
+    let drops = std::rc::Rc::new(());
+    let mut hv = HeaderVec::from_fn((), 4, |i| (i, drops.clone()));
+    assert_eq!(hv.pop_front().map(|(i, _)| i), Some(0));
+    assert_eq!(hv.pop_front().map(|(i, _)| i), Some(1));
+    assert_eq!(std::rc::Rc::strong_count(&drops), 3);
+    hv.push((4, drops.clone()));
+    let order: Vec<_> = std::iter::from_fn(|| hv.pop_front().map(|(i, _)| i)).collect();
+    assert_eq!(order, [2, 3, 4]);
+    assert!(hv.is_empty());
+    assert_eq!(std::rc::Rc::strong_count(&drops), 1);
+}
+
+#[test]
+fn test_min_useful_capacity() {
+    #[derive(Clone, Copy)]
+    #[repr(align(64))]
+    struct OverAligned(#[allow(dead_code)] u8);
+
+    type Hv = HeaderVec<u8, OverAligned>;
+    assert_eq!(Hv::min_useful_capacity(), 1);
+    assert_eq!(HeaderVec::<u8, ()>::min_useful_capacity(), usize::MAX);
+
+    for requested in [0, 1, 2, 5] {
+        let mut hv = Hv::with_capacity(requested, 0);
+        let capacity = hv.capacity();
+        assert_eq!(capacity, requested.max(Hv::min_useful_capacity()));
+        let report = hv.memory_report();
+        assert_eq!(report.element_bytes_spare, capacity * 64);
+        assert!(report.total_bytes >= report.header_bytes + capacity * 64);
+        for _ in 0..capacity {
+            assert_eq!(hv.push(OverAligned(0)), None);
+        }
+        assert_eq!(hv.as_ptr() as usize % 64, 0);
+        assert_eq!(hv.capacity(), capacity);
+    }
+}
+
+#[test]
+fn test_clone_from_slice() {
+    let old = std::rc::Rc::new(());
+    let new = std::rc::Rc::new(());
+    let mut hv = HeaderVec::from_fn((), 3, |_| old.clone());
+    assert_eq!(std::rc::Rc::strong_count(&old), 4);
+    hv.clone_from_slice(&[new.clone(), new.clone(), new.clone()]);
+    assert_eq!(std::rc::Rc::strong_count(&old), 1);
+    assert_eq!(std::rc::Rc::strong_count(&new), 4);
+    assert!(hv.as_slice().iter().all(|rc| std::rc::Rc::ptr_eq(rc, &new)));
+}
+
+#[test]
+#[should_panic]
+fn test_clone_from_slice_length_mismatch() {
+    let mut hv = HeaderVec::from_fn((), 3, |i| i);
+    hv.clone_from_slice(&[1, 2]);
+}
+
+fn check_contiguous<T: Default>(count: usize) {
+    assert!(HeaderVec::<u8, T>::elements_are_contiguous());
+    let hv = HeaderVec::from_fn(0u8, count, |_| T::default());
+    let slice = hv.as_slice();
+    assert_eq!(hv.as_ptr() as usize % core::mem::align_of::<T>(), 0);
+    for (i, e) in slice.iter().enumerate() {
+        assert_eq!(e as *const T, hv.as_ptr().wrapping_add(i));
+    }
+    let range = hv.as_ptr_range();
+    assert_eq!(
+        range.end as usize - range.start as usize,
+        count * core::mem::size_of::<T>()
+    );
+}
+
+#[test]
+fn test_elements_are_contiguous() {
+    #[derive(Default)]
+    #[repr(align(32))]
+    struct Simd(#[allow(dead_code)] [f32; 8]);
+
+    check_contiguous::<u8>(17);
+    check_contiguous::<u64>(9);
+    check_contiguous::<u128>(5);
+    check_contiguous::<Simd>(4);
+    check_contiguous::<(u8, u16)>(7);
+}
+
+#[test]
+fn test_drain_with() {
+    let mut hv = HeaderVec::<(), usize>::with_capacity(64, ());
+    hv.extend_from_slice(&(0..20).collect::<Vec<_>>());
+    let drained: Vec<_> = hv.drain_with(5..15, false).collect();
+    assert_eq!(drained, (5..15).collect::<Vec<_>>());
+    assert_eq!(hv.capacity(), 64);
+    assert_eq!(hv.len(), 10);
+
+    let mut drain = hv.drain_with(..5, true);
+    assert_eq!(drain.next(), Some(0));
+    drop(drain);
+    assert_eq!(hv.as_slice(), [15, 16, 17, 18, 19]);
+    assert_eq!(hv.capacity(), 5);
+}
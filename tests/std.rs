@@ -0,0 +1,15 @@
+#![cfg(feature = "std")]
+extern crate std;
+
+use header_vec::*;
+use std::io::Write;
+
+#[test]
+fn test_io_write() {
+    let mut hv = HeaderVec::<_, u8>::new("bytes");
+    write!(hv, "{}-{:x}", 42, 255u8).unwrap();
+    hv.write_all(b"!").unwrap();
+    hv.flush().unwrap();
+    assert_eq!(hv.as_slice(), b"42-ff!");
+    assert_eq!(*hv, "bytes");
+}